@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use cell_sim::cell::ReproductionMode;
+use cell_sim::scene::WorldBounds;
+
+const USAGE: &str = "usage: cell_sim [--headless] [--world-size <width>x<height>] [--seed <u64>] \
+                     [--components <path>]... [--genome <path>]... [--reproduction asexual|sexual] \
+                     [--load <path>] [--snapshot <path>] [--autosave <seconds>] [--lineage <dir>] \
+                     [--stats <path>] [--stats-interval <seconds>]";
+
+/// Command line arguments.
+#[derive(Default, Debug)]
+pub struct Args {
+    /// Component definitions to register, see [cell_sim::cell::load_components].
+    pub components: Vec<PathBuf>,
+    /// Genomes to seed the initial cells with, see [cell_sim::cell::SeedGenomes].
+    pub genomes: Vec<PathBuf>,
+    /// Seed for [cell_sim::cell::SimRng]. A random one is picked if not given.
+    pub seed: Option<u64>,
+    /// How cells reproduce. Defaults to [ReproductionMode::Asexual].
+    pub reproduction: Option<ReproductionMode>,
+    /// Run without a window or renderer.
+    pub headless: bool,
+    /// Size of the world. Defaults to [WorldBounds::default].
+    pub world_bounds: Option<WorldBounds>,
+    /// Snapshot to resume from, see [cell_sim::cell::PendingSnapshot].
+    pub load: Option<PathBuf>,
+    /// Where snapshots are saved while running, see [cell_sim::cell::SnapshotSettings].
+    pub snapshot: Option<PathBuf>,
+    /// Seconds between automatic snapshots and lineage exports.
+    pub autosave: Option<f32>,
+    /// Where the lineage is exported to, see [cell_sim::cell::LineageSettings].
+    pub lineage: Option<PathBuf>,
+    /// Where population statistics are recorded, see [cell_sim::stats::StatsSettings].
+    pub stats: Option<PathBuf>,
+    /// Simulated seconds between statistics samples.
+    pub stats_interval: Option<f64>,
+}
+
+impl Args {
+    /// Parses the process arguments, exiting with a usage message if they are invalid.
+    pub fn parse() -> Self {
+        Self::parse_from(std::env::args().skip(1)).unwrap_or_else(|e| {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        })
+    }
+
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+            match arg.as_str() {
+                "--components" => parsed.components.push(value()?.into()),
+                "--genome" => parsed.genomes.push(value()?.into()),
+                "--seed" => {
+                    let seed = value()?;
+                    parsed.seed = Some(seed.parse().map_err(|_| format!("invalid seed {}", seed))?);
+                }
+                "--reproduction" => parsed.reproduction = Some(parse_reproduction(&value()?)?),
+                "--headless" => parsed.headless = true,
+                "--world-size" => parsed.world_bounds = Some(parse_world_size(&value()?)?),
+                "--load" => parsed.load = Some(value()?.into()),
+                "--snapshot" => parsed.snapshot = Some(value()?.into()),
+                "--lineage" => parsed.lineage = Some(value()?.into()),
+                "--stats" => parsed.stats = Some(value()?.into()),
+                "--stats-interval" => {
+                    let seconds = value()?;
+                    parsed.stats_interval = Some(
+                        seconds
+                            .parse()
+                            .ok()
+                            .filter(|s: &f64| *s > 0.)
+                            .ok_or(format!("invalid stats interval {}", seconds))?,
+                    );
+                }
+                "--autosave" => {
+                    let seconds = value()?;
+                    parsed.autosave = Some(
+                        seconds
+                            .parse()
+                            .ok()
+                            .filter(|s: &f32| *s > 0.)
+                            .ok_or(format!("invalid autosave interval {}", seconds))?,
+                    );
+                }
+                _ => return Err(format!("unknown argument {}", arg)),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Parses `asexual` or `sexual`.
+fn parse_reproduction(mode: &str) -> Result<ReproductionMode, String> {
+    match mode {
+        "asexual" => Ok(ReproductionMode::Asexual),
+        "sexual" => Ok(ReproductionMode::Sexual),
+        _ => Err(format!("invalid reproduction mode {}", mode)),
+    }
+}
+
+/// Parses `<width>x<height>`, for instance `1920x1080`.
+fn parse_world_size(size: &str) -> Result<WorldBounds, String> {
+    let invalid = || format!("invalid world size {}", size);
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let width: f32 = width.parse().map_err(|_| invalid())?;
+    let height: f32 = height.parse().map_err(|_| invalid())?;
+    if width <= 0. || height <= 0. {
+        return Err(invalid());
+    }
+
+    Ok(WorldBounds { width, height })
+}
@@ -0,0 +1,165 @@
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+
+use crate::cell::{Cell, CellId, Lineage};
+use crate::inspector::SelectedCell;
+
+/// Lets a camera be panned by dragging with the right mouse button, zoomed with the scroll wheel
+/// and made to follow cells, see [CameraFollow].
+#[derive(Component)]
+pub struct CameraController {
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    /// Zoom change per line scrolled.
+    pub zoom_speed: f32,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            min_zoom: 0.1,
+            max_zoom: 10.,
+            zoom_speed: 0.1,
+        }
+    }
+}
+
+/// What the camera keeps centred on.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraFollow {
+    #[default]
+    Free,
+    /// Stops following once the cell dies.
+    Cell(Entity),
+    /// Follows the cell, and once it dies, its youngest living descendant of `root`.
+    Lineage { entity: Entity, root: CellId },
+}
+
+pub fn pan_and_zoom_camera(
+    buttons: Res<Input<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    mut follow: ResMut<CameraFollow>,
+    mut cameras: Query<(
+        &CameraController,
+        &mut Transform,
+        &mut OrthographicProjection,
+    )>,
+) {
+    let drag: Vec2 = match buttons.pressed(MouseButton::Right) {
+        true => motion.read().map(|motion| motion.delta).sum(),
+        false => {
+            motion.clear();
+            Vec2::ZERO
+        }
+    };
+    let scroll: f32 = wheel
+        .read()
+        .map(|wheel| match wheel.unit {
+            MouseScrollUnit::Line => wheel.y,
+            // Roughly one line.
+            MouseScrollUnit::Pixel => wheel.y / 20.,
+        })
+        .sum();
+
+    // Dragging takes the camera back from whatever it was following.
+    if drag != Vec2::ZERO {
+        *follow = CameraFollow::Free;
+    }
+    for (controller, mut transform, mut projection) in cameras.iter_mut() {
+        transform.translation.x -= drag.x * projection.scale;
+        transform.translation.y += drag.y * projection.scale;
+        if scroll != 0. {
+            projection.scale = (projection.scale * (1. - scroll * controller.zoom_speed))
+                .clamp(controller.min_zoom, controller.max_zoom);
+        }
+    }
+}
+
+/// F follows the selected cell, shift+F follows its lineage, escape stops following.
+pub fn toggle_camera_follow(
+    keys: Res<Input<KeyCode>>,
+    selected: Res<SelectedCell>,
+    cells: Query<&Cell>,
+    mut follow: ResMut<CameraFollow>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        *follow = CameraFollow::Free;
+    }
+    if !keys.just_pressed(KeyCode::F) {
+        return;
+    }
+    let Some(entity) = selected.0 else {
+        return;
+    };
+    *follow = match keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        true => match cells.get(entity) {
+            Ok(cell) => CameraFollow::Lineage {
+                entity,
+                root: cell.data.lineage.id,
+            },
+            Err(_) => return,
+        },
+        false => CameraFollow::Cell(entity),
+    };
+}
+
+pub fn follow_camera_target(
+    mut follow: ResMut<CameraFollow>,
+    cells: Query<(Entity, &Cell, &Transform)>,
+    lineage: Res<Lineage>,
+    mut cameras: Query<&mut Transform, (With<CameraController>, Without<Cell>)>,
+) {
+    let entity = match *follow {
+        CameraFollow::Free => return,
+        CameraFollow::Cell(entity) => entity,
+        CameraFollow::Lineage { entity, root } => {
+            if cells.contains(entity) {
+                entity
+            } else {
+                match youngest_descendant(&cells, &lineage, root) {
+                    Some(entity) => {
+                        *follow = CameraFollow::Lineage { entity, root };
+                        entity
+                    }
+                    None => {
+                        *follow = CameraFollow::Free;
+                        return;
+                    }
+                }
+            }
+        }
+    };
+    let Ok((_, _, target)) = cells.get(entity) else {
+        *follow = CameraFollow::Free;
+        return;
+    };
+
+    for mut transform in cameras.iter_mut() {
+        transform.translation.x = target.translation.x;
+        transform.translation.y = target.translation.y;
+    }
+}
+
+/// The most recently born living cell descending from `root`.
+fn youngest_descendant(
+    cells: &Query<(Entity, &Cell, &Transform)>,
+    lineage: &Lineage,
+    root: CellId,
+) -> Option<Entity> {
+    let descends = |mut record: Option<CellId>| {
+        while let Some(id) = record {
+            if id == root {
+                return true;
+            }
+            record = lineage.get(id).and_then(|record| record.parent);
+        }
+        false
+    };
+
+    cells
+        .iter()
+        .filter(|(_, cell, _)| descends(Some(cell.data.lineage.id)))
+        .max_by(|(_, a, _), (_, b, _)| a.data.lineage.born.total_cmp(&b.data.lineage.born))
+        .map(|(entity, ..)| entity)
+}
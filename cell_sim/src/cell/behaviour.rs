@@ -0,0 +1,460 @@
+use serde::{Deserialize, Serialize};
+
+use super::cell_base::{CellData, Offspring, SECRETED_SIGNALS, SIGNAL_PROTEINS};
+use super::cell_internals::{Polysaccharide, SignalProtein};
+use super::component_instances::ComponentBuilderProps;
+
+/// Complexity of the [Polysaccharide]s made by reactions.
+pub const POLYSACCHARIDE_COMPLEXITY: f32 = 2.;
+
+/// Something a [Reaction] can use up or make.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Substance {
+    Atp,
+    Glucose,
+    /// Used up oldest first. Made with [POLYSACCHARIDE_COMPLEXITY].
+    Polysaccharides,
+    Proteins,
+    AminoAcids,
+    Nucleotides,
+    /// Glucose on the cell's tile of the [crate::nutrients::NutrientField]. Only makes sense for
+    /// membrane components.
+    ExternalGlucose,
+}
+
+impl Substance {
+    fn available(self, cell: &CellData) -> f32 {
+        match self {
+            Self::Atp => cell.base.atp,
+            Self::Glucose => cell.base.glucose,
+            Self::Polysaccharides => cell.base.polysaccharides.iter().map(|p| p.amount).sum(),
+            Self::Proteins => cell.base.proteins,
+            Self::AminoAcids => cell.base.amino_acids,
+            Self::Nucleotides => cell.base.nucleotides,
+            Self::ExternalGlucose => cell.surroundings.glucose,
+        }
+    }
+
+    /// Removes `amount`, which must not be more than is [Substance::available]. Never leaves less
+    /// than zero, in case rounding makes `amount` a little more than what is left.
+    fn take(self, cell: &mut CellData, amount: f32) {
+        let take = |value: &mut f32| *value = (*value - amount).max(0.);
+        match self {
+            Self::Atp => take(&mut cell.base.atp),
+            Self::Glucose => take(&mut cell.base.glucose),
+            Self::Polysaccharides => {
+                let mut left = amount;
+                for polysaccharide in cell.base.polysaccharides.iter_mut() {
+                    let taken = left.min(polysaccharide.amount);
+                    polysaccharide.amount -= taken;
+                    left -= taken;
+                }
+                cell.base.polysaccharides.retain(|p| p.amount > 0.);
+            }
+            Self::Proteins => take(&mut cell.base.proteins),
+            Self::AminoAcids => take(&mut cell.base.amino_acids),
+            Self::Nucleotides => take(&mut cell.base.nucleotides),
+            Self::ExternalGlucose => {
+                cell.surroundings.take_glucose(amount);
+            }
+        }
+    }
+
+    fn add(self, cell: &mut CellData, amount: f32) {
+        match self {
+            Self::Atp => cell.base.atp += amount,
+            Self::Glucose => cell.base.glucose += amount,
+            Self::Polysaccharides => match cell.base.polysaccharides.last_mut() {
+                Some(last) if last.complexity == POLYSACCHARIDE_COMPLEXITY => last.amount += amount,
+                _ => cell.base.polysaccharides.push(Polysaccharide {
+                    complexity: POLYSACCHARIDE_COMPLEXITY,
+                    amount,
+                }),
+            },
+            Self::Proteins => cell.base.proteins += amount,
+            Self::AminoAcids => cell.base.amino_acids += amount,
+            Self::Nucleotides => cell.base.nucleotides += amount,
+            Self::ExternalGlucose => cell.surroundings.release_glucose(amount),
+        }
+    }
+}
+
+/// An amount of a [Substance], per unit a [Reaction] runs.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Reagent {
+    pub substance: Substance,
+    pub amount: f32,
+}
+
+impl Reagent {
+    pub const fn new(substance: Substance, amount: f32) -> Self {
+        Self { substance, amount }
+    }
+}
+
+/// Turns inputs into outputs. Runs up to `rate * dt * size` units per step, scaled by the one
+/// value the component reads from its [super::WeightList], and only as far as the inputs last.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Reaction {
+    #[serde(default)]
+    pub inputs: Vec<Reagent>,
+    /// Scaled by the component's efficiency.
+    #[serde(default)]
+    pub outputs: Vec<Reagent>,
+    /// Not scaled by the component's efficiency.
+    #[serde(default)]
+    pub byproducts: Vec<Reagent>,
+    #[serde(default = "default_rate")]
+    pub rate: f32,
+}
+
+fn default_rate() -> f32 {
+    1.
+}
+
+impl Reaction {
+    /// Returns the units run.
+    fn run(&self, weight: f32, speed: f32, efficiency: f32, cell: &mut CellData, dt: f32) -> f32 {
+        // Reactions only run forwards, so nothing is made out of nothing.
+        let mut amount = weight.max(0.) * dt * speed * self.rate;
+        for input in self.inputs.iter().filter(|input| input.amount > 0.) {
+            amount = amount.min(input.substance.available(cell).max(0.) / input.amount);
+        }
+        if amount <= 0. {
+            return 0.;
+        }
+
+        for input in &self.inputs {
+            input.substance.take(cell, amount * input.amount);
+        }
+        for output in &self.outputs {
+            output
+                .substance
+                .add(cell, amount * output.amount * efficiency);
+        }
+        for byproduct in &self.byproducts {
+            byproduct.substance.add(cell, amount * byproduct.amount);
+        }
+
+        amount
+    }
+}
+
+/// How efficient a component is given its size and proteins.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EfficiencyCurve {
+    /// `max * tanh(proteins / size)`. The idea is that a process needs a set amount of proteins
+    /// to construct and a set amount of space, so proteins / size is the amount of proteins in the
+    /// process, assuming one unit of size is one process.
+    Saturating { max: f32 },
+    /// The same no matter the proteins.
+    Constant(f32),
+}
+
+impl Default for EfficiencyCurve {
+    fn default() -> Self {
+        Self::Saturating { max: 0.9 }
+    }
+}
+
+impl EfficiencyCurve {
+    pub fn efficiency(&self, size: f32, proteins: f32) -> f32 {
+        match *self {
+            Self::Saturating { max } => f32::tanh(proteins / size) * max,
+            Self::Constant(efficiency) => efficiency,
+        }
+    }
+}
+
+/// What a component does each step. Interpreted by [super::CellComponent::run], so components can
+/// be defined in config files and saved with the genome, see [super::ComponentInfo].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Behaviour {
+    Reaction(Reaction),
+    /// Pushes the cell along its heading and turns it, reading thrust and torque. Negative thrust
+    /// swims backwards, negative torque turns clockwise.
+    Flagella {
+        /// Turning speed gained per unit of torque, relative to speed gained per unit of thrust.
+        turn_rate: f32,
+        /// ATP per squared unit of thrust and torque, per unit of cell size.
+        atp_cost: f32,
+    },
+    /// Senses the cell's [super::Surroundings]. Each channel is written to the signal protein of
+    /// the same index, scaled by how strongly the receptor responds to it, so other components
+    /// can react to it through their [super::WeightList] sensitivities.
+    Receptor {
+        /// ATP per unit of size per second.
+        atp_cost: f32,
+    },
+    /// Lets the cell engulf smaller cells it touches while active, see
+    /// [super::predation::engulf_cells].
+    Phagocytosis {
+        /// ATP per unit of size per second while active.
+        atp_cost: f32,
+    },
+    /// Gene regulator making and breaking down the signal proteins `first..first + count`, reading
+    /// one value per signal. Positive values make the signal out of amino acids, negative values
+    /// break it down into amino acids again. Other components react to the signals through their
+    /// [super::WeightList] sensitivities, so regulators let cells build feedback loops,
+    /// oscillators and memory.
+    Regulator {
+        first: usize,
+        count: usize,
+        /// Fraction broken down, or units made per unit of size, per second at full strength.
+        rate: f32,
+        /// ATP per unit of signal made.
+        atp_cost: f32,
+    },
+    /// Releases the signal proteins `first..first + count` into the
+    /// [crate::signals::SignalField] as secreted signals `0..count`. Reads one value per signal,
+    /// the fraction of it released per second.
+    Secretion {
+        first: usize,
+        count: usize,
+        /// ATP per unit of signal released.
+        atp_cost: f32,
+    },
+    /// Takes secreted signals `0..count` up from the [crate::signals::SignalField] into the signal
+    /// proteins `first..first + count`. Reads one value per signal, how permeable the membrane is
+    /// to it. Signals only flow in while the concentration outside is higher than inside. Both
+    /// are amounts per unit of cell size: inside is [SignalProtein::strength], outside is the
+    /// cell's share of its tile, see [super::Surroundings::signals], over its size. Cells can
+    /// thus sense how many others are secreting around them.
+    SignalImport {
+        first: usize,
+        count: usize,
+        /// Flow per unit of size and of concentration difference per second at full permeability.
+        rate: f32,
+    },
+    /// Splits off a daughter cell once the cell has enough ATP and is not still holding one, see
+    /// [super::CellData::offspring]. Reads the share of the internals the daughter gets.
+    Division {
+        atp_threshold: f32,
+        atp_cost: f32,
+    },
+}
+
+impl Behaviour {
+    /// Whether the behaviour acts on the cell's surroundings, so it only works in the membrane.
+    pub fn needs_membrane(&self) -> bool {
+        match self {
+            Self::Reaction(reaction) => reaction
+                .inputs
+                .iter()
+                .chain(&reaction.outputs)
+                .chain(&reaction.byproducts)
+                .any(|reagent| reagent.substance == Substance::ExternalGlucose),
+            Self::Flagella { .. }
+            | Self::Receptor { .. }
+            | Self::Phagocytosis { .. }
+            | Self::Secretion { .. }
+            | Self::SignalImport { .. } => true,
+            Self::Regulator { .. } | Self::Division { .. } => false,
+        }
+    }
+
+    /// Checks that the signals the behaviour works on exist.
+    pub fn validate(&self) -> Result<(), String> {
+        let (first, count, secreted) = match *self {
+            Self::Regulator { first, count, .. } => (first, count, false),
+            Self::Secretion { first, count, .. } | Self::SignalImport { first, count, .. } => {
+                (first, count, true)
+            }
+            _ => return Ok(()),
+        };
+        if first.saturating_add(count) > SIGNAL_PROTEINS {
+            return Err(format!(
+                "signal proteins {}..{} out of range, there are {}",
+                first,
+                first.saturating_add(count),
+                SIGNAL_PROTEINS
+            ));
+        }
+        if secreted && count > SECRETED_SIGNALS {
+            return Err(format!(
+                "{} secreted signals out of range, there are {}",
+                count, SECRETED_SIGNALS
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Amount of values read from the component's [super::WeightList] each step.
+    pub fn args(&self) -> usize {
+        match self {
+            Self::Flagella { .. } => 2,
+            Self::Receptor { .. } => super::SENSE_CHANNELS,
+            Self::Regulator { count, .. }
+            | Self::Secretion { count, .. }
+            | Self::SignalImport { count, .. } => *count,
+            Self::Reaction(_) | Self::Phagocytosis { .. } | Self::Division { .. } => 1,
+        }
+    }
+
+    /// Runs the behaviour for `dt` and returns how much work the component did, which wears it
+    /// down, see [super::StateVariable::wear].
+    pub fn run(
+        &self,
+        props: &ComponentBuilderProps,
+        efficiency: f32,
+        cell: &mut CellData,
+        dt: f32,
+    ) -> f32 {
+        let speed = props.size;
+        let args =
+            props
+                .weightlist
+                .get_split_vals(cell.size, &cell.base.signal_proteins, self.args());
+
+        match *self {
+            Self::Reaction(ref reaction) => reaction.run(args[0], speed, efficiency, cell, dt),
+            Self::Flagella {
+                turn_rate,
+                atp_cost,
+            } => {
+                let mut thrust = args[0] * dt * speed;
+                let mut torque = args[1] * dt * speed;
+
+                let mut cost = (thrust * thrust + torque * torque) * cell.base.size() * atp_cost;
+                if cell.base.atp < cost {
+                    let scale = (cell.base.atp.max(0.) / cost).sqrt();
+                    thrust *= scale;
+                    torque *= scale;
+                    cost = cell.base.atp.max(0.);
+                }
+                cell.base.atp -= cost;
+                cell.base.glucose += thrust.abs() / 50.;
+
+                cell.velocity += cell.heading * thrust * efficiency;
+                cell.angular_velocity += torque * efficiency * turn_rate;
+
+                thrust.abs() + torque.abs()
+            }
+            Self::Receptor { atp_cost } => {
+                let senses = cell.surroundings.senses();
+                // Written every step, so a channel the receptor stops responding to is cleared
+                // instead of holding its last reading.
+                for (channel, (sense, response)) in senses.iter().zip(args).enumerate() {
+                    cell.base
+                        .set_signal(channel, sense * response.max(0.) * efficiency);
+                }
+                cell.base.atp -= props.size * dt * atp_cost;
+
+                props.size * dt
+            }
+            Self::Phagocytosis { atp_cost } => {
+                if args[0] <= 0. {
+                    return 0.;
+                }
+                cell.engulf = cell.engulf.max(efficiency);
+                cell.base.atp -= props.size * dt * atp_cost;
+
+                props.size * dt
+            }
+            Self::Regulator {
+                first,
+                count: _,
+                rate,
+                atp_cost,
+            } => {
+                let mut work = 0.;
+                for (i, value) in args.into_iter().enumerate() {
+                    let index = first + i;
+                    let current = cell
+                        .base
+                        .signal_proteins
+                        .get(index)
+                        .map_or(0., SignalProtein::amount);
+                    if value > 0. {
+                        let amount = (value * dt * speed * rate)
+                            .min(cell.base.amino_acids.max(0.))
+                            .min(cell.base.atp.max(0.) / atp_cost);
+                        cell.base.amino_acids -= amount;
+                        cell.base.atp -= amount * atp_cost;
+                        cell.base.set_signal(index, current + amount * efficiency);
+                        work += amount;
+                    } else if value < 0. {
+                        let amount = current * (-value * dt * rate).min(1.);
+                        cell.base.set_signal(index, current - amount);
+                        cell.base.amino_acids += amount;
+                        work += amount;
+                    }
+                }
+
+                work
+            }
+            Self::Secretion {
+                first,
+                count: _,
+                atp_cost,
+            } => {
+                let mut work = 0.;
+                for (channel, value) in args.into_iter().enumerate() {
+                    let index = first + channel;
+                    let current = cell
+                        .base
+                        .signal_proteins
+                        .get(index)
+                        .map_or(0., SignalProtein::amount);
+                    if value <= 0. || current <= 0. {
+                        continue;
+                    }
+                    let amount =
+                        (current * (value * dt).min(1.)).min(cell.base.atp.max(0.) / atp_cost);
+                    cell.base.atp -= amount * atp_cost;
+                    cell.base.set_signal(index, current - amount);
+                    cell.surroundings.release_signal(channel, amount);
+                    work += amount;
+                }
+
+                work
+            }
+            Self::SignalImport {
+                first,
+                count: _,
+                rate,
+            } => {
+                let mut work = 0.;
+                for (channel, value) in args.into_iter().enumerate() {
+                    let index = first + channel;
+                    let current = cell
+                        .base
+                        .signal_proteins
+                        .get(index)
+                        .map_or(0., SignalProtein::amount);
+                    let inside = SignalProtein::new(current).strength(cell.size);
+                    let outside = cell.surroundings.signals[channel] / cell.size;
+                    if value <= 0. || outside <= inside {
+                        continue;
+                    }
+                    let flow = value * dt * speed * rate * efficiency * (outside - inside);
+                    let amount = cell.surroundings.take_signal(channel, flow);
+                    cell.base.set_signal(index, current + amount);
+                    work += amount;
+                }
+
+                work
+            }
+            Self::Division {
+                atp_threshold,
+                atp_cost,
+            } => {
+                // A cell holding a daughter waits for it to be spawned or mated before dividing
+                // again, so it does not pay for divisions that are only reabsorbed.
+                if cell.base.atp < atp_threshold || !cell.offspring.is_empty() {
+                    return 0.;
+                }
+                cell.base.atp -= atp_cost;
+                // Share of the mother's internals the daughter gets, between 0.1 and 0.9.
+                let internals = cell.base.split_off(0.5 + args[0] * 0.4);
+                cell.offspring.push(Offspring {
+                    rna: cell.rna.clone(),
+                    internals,
+                });
+
+                1.
+            }
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use super::cell_components::{run_components, CellComponent};
-use super::rna::{build_rna, RNA};
-use super::weights::WeightList;
+use super::lineage::LineageRecord;
+use super::rna::RNA;
+use super::rng::CellRng;
 
 use bevy::{log, prelude::*};
+use serde::{Deserialize, Serialize};
 
 use super::cell_internals::CellInternals;
 
@@ -37,17 +39,157 @@ pub enum CellComponentType {
 
 /// Represents the data of the cell. This data is seperarate so that it can more freely be mutated
 /// by the [CellComponent]s.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CellData {
     /// Accelleration the cell can move at
     pub speed: f32,
     pub base: CellInternals,
     pub velocity: Vec2,
-    pub new_cells: Vec<Cell>,
+    /// Direction the cell is facing, as a unit vector. Read from the cell's rotation every tick.
+    pub heading: Vec2,
+    /// Counterclockwise turning speed in radians.
+    pub angular_velocity: f32,
+    /// Daughter cells produced this frame. These are mutated and spawned by
+    /// [super::ctl::reproduce_cells].
+    pub offspring: Vec<Offspring>,
     pub size: f32,
     pub rna: RNA,
+    /// Who the cell is and where it comes from, see [super::lineage::Lineage].
+    pub lineage: LineageRecord,
+    /// Every random draw made by the cell or its [CellComponent]s goes through this.
+    pub rng: CellRng,
+    /// Refreshed every tick, so it is not saved.
+    #[serde(skip)]
+    pub surroundings: Surroundings,
+    /// Fraction of the internals of a smaller cell absorbed when engulfing it, or zero if the cell
+    /// cannot engulf. Set by phagocytosis components every step, see
+    /// [super::predation::engulf_cells].
+    #[serde(skip)]
+    pub engulf: f32,
+}
+
+/// A daughter cell that has split off but was not spawned yet.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Offspring {
+    pub rna: RNA,
+    /// The daughter's share of the mother's [CellInternals].
+    pub internals: CellInternals,
+}
+
+/// What the environment around the cell has to offer this tick. Filled in by
+/// [crate::nutrients::share_nutrients] and only meant to be used by membrane [CellComponent]s.
+#[derive(Clone, Default)]
+pub struct Surroundings {
+    /// Glucose the cell can still take from its tile of the [crate::nutrients::NutrientField].
+    pub glucose: f32,
+    /// Glucose taken this tick, removed from the field by [crate::nutrients::collect_nutrients].
+    /// Negative if the cell released more than it took.
+    pub glucose_taken: f32,
+    /// Glucose on the cell's tile, before it is shared with other cells.
+    pub concentration: f32,
+    /// Direction glucose increases in, per unit of distance.
+    pub gradient: Vec2,
+    /// Number of other cells on the same tile.
+    pub crowding: f32,
+    /// Secreted signals the cell can still take from its tile of the
+    /// [crate::signals::SignalField], shared like [Surroundings::glucose].
+    pub signals: [f32; SECRETED_SIGNALS],
+    /// Secreted signals taken this tick, negative if the cell released more than it took.
+    /// Applied to the field by [crate::signals::collect_signals].
+    pub signals_taken: [f32; SECRETED_SIGNALS],
+}
+
+/// Number of things a cell can sense, see [Surroundings::senses].
+pub const SENSE_CHANNELS: usize = 6;
+/// Number of signal proteins made by gene regulators, see [super::Behaviour::Regulator]. They come
+/// after the [SENSE_CHANNELS].
+pub const REGULATORY_SIGNALS: usize = 8;
+/// Number of signals cells can secrete into the [crate::signals::SignalField]. Secreted signal `i`
+/// is made from and imported into regulatory signal `i`, see [super::Behaviour::Secretion].
+pub const SECRETED_SIGNALS: usize = 4;
+/// Number of signal proteins every cell has, and that [super::Sensitivity::index] can refer to.
+pub const SIGNAL_PROTEINS: usize = SENSE_CHANNELS + REGULATORY_SIGNALS;
+
+impl Surroundings {
+    /// Takes up to `amount` glucose from the environment and returns how much was taken.
+    pub fn take_glucose(&mut self, amount: f32) -> f32 {
+        let amount = amount.clamp(0., self.glucose);
+        self.glucose -= amount;
+        self.glucose_taken += amount;
+        amount
+    }
+
+    /// Releases `amount` glucose onto the cell's tile.
+    pub fn release_glucose(&mut self, amount: f32) {
+        self.glucose_taken -= amount;
+    }
+
+    /// Takes up to `amount` of a secreted signal from the environment and returns how much was
+    /// taken.
+    pub fn take_signal(&mut self, channel: usize, amount: f32) -> f32 {
+        let amount = amount.clamp(0., self.signals[channel]);
+        self.signals[channel] -= amount;
+        self.signals_taken[channel] += amount;
+        amount
+    }
+
+    /// Releases `amount` of a secreted signal onto the cell's tile.
+    pub fn release_signal(&mut self, channel: usize, amount: f32) {
+        self.signals_taken[channel] -= amount;
+    }
+
+    /// Readings of everything a cell can sense, never negative. Receptors write channel `i` into
+    /// signal protein `i`, see [super::Behaviour::Receptor].
+    ///
+    /// 0. Glucose concentration.
+    /// 1. to 4. Glucose gradient towards +x, -x, +y and -y.
+    /// 5. Crowding.
+    pub fn senses(&self) -> [f32; SENSE_CHANNELS] {
+        [
+            self.concentration,
+            self.gradient.x.max(0.),
+            (-self.gradient.x).max(0.),
+            self.gradient.y.max(0.),
+            (-self.gradient.y).max(0.),
+            self.crowding,
+        ]
+    }
+}
+
+impl CellData {
+    /// Takes the internals of every pending [Offspring] back, for instance before the cell dies or
+    /// is engulfed, so they are not lost with it.
+    pub fn reabsorb_offspring(&mut self) {
+        for offspring in self.offspring.drain(..) {
+            self.base.absorb(&offspring.internals, 1.);
+        }
+    }
 }
 
 impl Cell {
+    /// Creates a cell without any components. See [super::component_instances::create_cell] to
+    /// build the components from the [RNA].
+    pub fn new(rna: RNA, lineage: LineageRecord, rng: CellRng) -> Self {
+        Self {
+            internal_components: vec![],
+            membrane_components: vec![],
+            data: CellData {
+                size: 1.,
+                speed: 1.,
+                base: CellInternals::default(),
+                velocity: Vec2::new(0., 0.),
+                heading: Vec2::X,
+                angular_velocity: 0.,
+                offspring: Vec::new(),
+                rna,
+                lineage,
+                rng,
+                surroundings: Surroundings::default(),
+                engulf: 0.,
+            },
+        }
+    }
+
     pub fn size(&self) -> f32 {
         let mut size = self.data.speed / 4. + self.data.base.size();
         for component in &self.internal_components {
@@ -66,13 +208,23 @@ impl Cell {
 
     /// Update the cell. This will run all the [InternalComponent]s and [MembraneComponent]s.
     pub fn update(&mut self, dt: f32) {
-        self.data.base.glucose += dt * 0.2;
         self.data.base.atp -= dt * 1.7 - dt * self.size() * 0.4;
+        self.data.engulf = 0.;
         run_components(&mut self.internal_components, &mut self.data, dt);
         run_components(&mut self.membrane_components, &mut self.data, dt);
         self.data.size = self.size();
     }
 
+    /// Runs [Cell::update] `steps` times, stopping early once the cell runs out of ATP.
+    pub fn metabolize(&mut self, steps: u32, dt: f32) {
+        for _ in 0..steps {
+            if self.data.base.atp <= 0.1 {
+                break;
+            }
+            self.update(dt);
+        }
+    }
+
     pub fn inject_component(&mut self, component: CellComponentType) {
         match component {
             CellComponentType::Internal(component) => {
@@ -84,20 +236,3 @@ impl Cell {
         }
     }
 }
-
-impl Default for Cell {
-    fn default() -> Self {
-        Self {
-            internal_components: vec![],
-            membrane_components: vec![],
-            data: CellData {
-                size: 1.,
-                speed: 1.,
-                base: CellInternals::default(),
-                velocity: Vec2::new(0., 0.),
-                new_cells: Vec::new(),
-                rna: build_rna(&WeightList::default(), 1., &Vec::new()),
-            },
-        }
-    }
-}
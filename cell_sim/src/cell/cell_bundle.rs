@@ -1,14 +1,22 @@
 use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
 use bevy::{log, prelude::*};
 use bevy_rapier2d::dynamics::{Damping, RigidBody, Velocity};
-use bevy_rapier2d::geometry::{Collider, ColliderMassProperties, Restitution};
+use bevy_rapier2d::geometry::{
+    ActiveEvents, Collider, ColliderMassProperties, CollidingEntities, Restitution,
+};
 
 use super::cell_base::Cell;
 
+/// Everything needed to simulate a cell. This does not include anything for rendering, see
+/// [CellMeshBundle], so that cells can be simulated without a renderer.
 #[derive(Bundle)]
 pub struct CellBundle {
-    pub material_mesh_bundle: MaterialMesh2dBundle<ColorMaterial>,
+    pub transform_bundle: TransformBundle,
     pub collider: Collider,
+    /// Contact events are needed to fill in [CellBundle::colliding_entities].
+    pub active_events: ActiveEvents,
+    /// Cells touching this one, so cells can be engulfed or mate no matter when contact started.
+    pub colliding_entities: CollidingEntities,
     pub collider_mass_properties: ColliderMassProperties,
     pub cell: Cell,
     pub damping: Damping,
@@ -19,23 +27,20 @@ pub struct CellBundle {
 
 const CELL_SIZE_MODIFIER: f32 = 0.2;
 
+impl Cell {
+    /// Radius of the cell's collider and mesh.
+    pub fn radius(&self) -> f32 {
+        self.size() * CELL_SIZE_MODIFIER
+    }
+}
+
 impl CellBundle {
-    pub fn new(
-        meshes: &mut Assets<Mesh>,
-        materials: &mut Assets<ColorMaterial>,
-        cell: Cell,
-        pos: Vec3,
-    ) -> Self {
+    pub fn new(cell: Cell, pos: Vec3) -> Self {
         Self {
-            material_mesh_bundle: MaterialMesh2dBundle {
-                mesh: meshes
-                    .add(shape::Circle::new(cell.size() * CELL_SIZE_MODIFIER).into())
-                    .into(),
-                material: materials.add(ColorMaterial::from(Color::PURPLE)),
-                transform: Transform::from_xyz(pos.x, pos.y, 0.),
-                ..default()
-            },
+            transform_bundle: TransformBundle::from(Transform::from_xyz(pos.x, pos.y, 0.)),
             collider: Collider::ball(cell.size() * CELL_SIZE_MODIFIER),
+            active_events: ActiveEvents::COLLISION_EVENTS,
+            colliding_entities: CollidingEntities::default(),
             collider_mass_properties: ColliderMassProperties::Density(1.),
             damping: Damping {
                 linear_damping: 1.,
@@ -49,21 +54,87 @@ impl CellBundle {
     }
 }
 
+/// Levels per colour channel of [CellRenderAssets::palette].
+const PALETTE_STEPS: usize = 8;
+
+/// Assets shared by every drawn cell, so drawing does not create new assets.
+#[derive(Resource)]
+pub struct CellRenderAssets {
+    /// Circle with a radius of one, scaled to the size of each cell.
+    pub mesh: Mesh2dHandle,
+    /// Colours quantised to [PALETTE_STEPS] levels of ATP (red) and glucose (green).
+    pub palette: Vec<Handle<ColorMaterial>>,
+}
+
+impl FromWorld for CellRenderAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Circle::new(1.).into())
+            .into();
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        let level = |i: usize| i as f32 / (PALETTE_STEPS - 1) as f32;
+        let palette = (0..PALETTE_STEPS * PALETTE_STEPS)
+            .map(|i| {
+                let color = Color::rgb(level(i / PALETTE_STEPS), level(i % PALETTE_STEPS), 0.);
+                materials.add(ColorMaterial::from(color))
+            })
+            .collect();
+
+        Self { mesh, palette }
+    }
+}
+
+impl CellRenderAssets {
+    pub fn material(&self, cell: &Cell) -> &Handle<ColorMaterial> {
+        let level =
+            |amount: f32| (amount.clamp(0., 1.) * (PALETTE_STEPS - 1) as f32).round() as usize;
+        let r = level(cell.data.base.atp);
+        let g = level(cell.data.base.glucose);
+
+        &self.palette[r * PALETTE_STEPS + g]
+    }
+}
+
+/// Marks the child entity drawing a cell. The mesh is kept off the cell itself because scaling the
+/// cell's [Transform] would scale its collider too.
+#[derive(Component)]
+pub struct CellMesh;
+
+/// What a [CellBundle] needs to be drawn. Spawned as a child of the cell by
+/// [super::ctl::attach_cell_meshes].
+#[derive(Bundle)]
+pub struct CellMeshBundle {
+    pub marker: CellMesh,
+    pub mesh: MaterialMesh2dBundle<ColorMaterial>,
+}
+
+impl CellMeshBundle {
+    pub fn new(assets: &CellRenderAssets, cell: &Cell) -> Self {
+        Self {
+            marker: CellMesh,
+            mesh: MaterialMesh2dBundle {
+                mesh: assets.mesh.clone(),
+                material: assets.material(cell).clone(),
+                transform: Transform::from_scale(Vec3::splat(cell.radius())),
+                ..Default::default()
+            },
+        }
+    }
+}
+
 pub fn update_cell_mesh(
-    cell: &mut Cell,
-    mesh: &mut Mesh2dHandle,
-    color: &mut Handle<ColorMaterial>,
-    mesh_assets: &mut ResMut<Assets<Mesh>>,
-    color_assets: &mut ResMut<Assets<ColorMaterial>>,
+    cell: &Cell,
+    transform: &mut Transform,
+    material: &mut Handle<ColorMaterial>,
+    assets: &CellRenderAssets,
 ) {
-    *mesh = mesh_assets
-        .add(shape::Circle::new(cell.size() * CELL_SIZE_MODIFIER).into())
-        .into();
-
-    let r = cell.data.base.atp.min(1.);
-    let g = cell.data.base.glucose.min(1.);
-    let new_color = ColorMaterial::from(Color::rgb(r, g, 0.));
-    *color = color_assets.add(new_color);
+    transform.scale = Vec3::splat(cell.radius());
+
+    let new_material = assets.material(cell);
+    if material != new_material {
+        *material = new_material.clone();
+    }
 }
 
 pub fn update_cell_physics(
@@ -71,9 +142,7 @@ pub fn update_cell_physics(
     collider: &mut Collider,
     collider_mass_properties: &mut ColliderMassProperties,
     damping: &mut Damping,
-    velocity: &mut Velocity,
 ) {
-    velocity.linvel = cell.data.velocity;
     *collider = Collider::ball(cell.size() * CELL_SIZE_MODIFIER);
     *collider_mass_properties = ColliderMassProperties::Density(cell.size());
     *damping = Damping {
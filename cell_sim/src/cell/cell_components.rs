@@ -1,54 +1,128 @@
 use std::sync::Arc;
 
 use super::cell_base::CellData;
+use super::component_instances::ComponentSpec;
+use super::registry::ComponentInfo;
 
 /// Iterates through all the [CellComponent]<T>s and runs them. This will update the
 /// componnents too.
 pub fn run_components(components: &mut Vec<CellComponent>, data: &mut CellData, dt: f32) {
-    // Vector of new components to replace if needed. We need this to avoid mutating the vector of
-    // CellComponent while iterating through it.
-    let mut replaced: Vec<(CellComponent, usize)> = Vec::with_capacity(components.len() / 4);
+    // New states of the components whose state changed. We need this to avoid mutating the
+    // vector of CellComponent while iterating through it.
+    let mut updated: Vec<(Vec<f32>, usize)> = Vec::with_capacity(components.len() / 4);
     let mut new: Vec<CellComponent> = Vec::new();
 
     for (counter, component) in components.iter().enumerate() {
-        // CellComponent::run will return a new CellComponent if it needs to update itself.
-        let result = (component.run)(data, dt);
-        if let Some(replaced_component) = result.0 {
-            replaced.push((replaced_component, counter))
+        // Only needed by components that can replicate.
+        let copies = match component.info.replication {
+            Some(_) => components
+                .iter()
+                .chain(&new)
+                .filter(|other| other.spec.id == component.spec.id)
+                .count(),
+            None => 0,
+        };
+        // CellComponent::run will return a new state if it needs to update itself.
+        let result = component.run(data, dt, copies);
+        if let Some(state) = result.0 {
+            updated.push((state, counter))
         }
         if let Some(new_components) = result.1 {
             new.extend(new_components);
         }
     }
 
-    // Replace the old components with the new ones.
-    for (component, index) in replaced {
-        components[index] = component;
+    // Replace the old states with the new ones.
+    for (state, index) in updated {
+        components[index].state = state;
     }
     components.extend(new);
 }
 
-pub type CellComponentFn =
-    Arc<dyn Fn(&mut CellData, f32) -> (Option<CellComponent>, Option<Vec<CellComponent>>)>;
 /// A physical component of a [Cell], either in the Membrane or Internal structure.
 /// [CellComponent::size] represents the space it takes up in either the membrane or internal
-/// structure. [CellComponent::run] is a function that sohuld be called each frame, potentially
-/// mutating [CellData].
+/// structure. [CellComponent::run] should be called each frame, potentially mutating [CellData].
+#[derive(Clone)]
 pub struct CellComponent {
     pub size: f32,
-    /// Function that should be called each frame. This function takes in [CellData] and a delta
-    /// and returns a [CellComponent] if it needs to update itself. The function will contain the
-    /// state of the [CellComponent].
-    pub run: CellComponentFn,
+    /// What this component was built from, so it can be rebuilt after being saved.
+    pub spec: ComponentSpec,
+    /// What kind of component this is and what it does.
+    pub info: Arc<ComponentInfo>,
+    /// How well the component works, from [ComponentInfo::efficiency].
+    pub efficiency: f32,
+    /// Values of [ComponentInfo::state], in the same order.
+    pub state: Vec<f32>,
 }
 
-impl Clone for CellComponent {
-    fn clone(&self) -> Self {
+impl CellComponent {
+    pub fn new(info: Arc<ComponentInfo>, spec: ComponentSpec) -> Self {
+        let props = &spec.props;
         Self {
-            size: self.size,
-            run: self.run.clone(),
+            size: info.size.unwrap_or(props.size),
+            efficiency: info.efficiency.efficiency(props.size, props.proteins),
+            state: info.state.iter().map(|variable| variable.initial).collect(),
+            info,
+            spec,
         }
     }
+
+    /// Names and values of the component's state, see [ComponentInfo::state].
+    pub fn state(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.info
+            .state
+            .iter()
+            .zip(&self.state)
+            .map(|(variable, value)| (&*variable.name, *value))
+    }
+
+    /// Runs the component's [super::Behaviour]. Returns the new [CellComponent::state] if it
+    /// changed, and copies of the component if it replicated. `copies` is the amount of
+    /// components of this kind the cell has, see [super::Replication::max_copies].
+    pub fn run(
+        &self,
+        data: &mut CellData,
+        dt: f32,
+        copies: usize,
+    ) -> (Option<Vec<f32>>, Option<Vec<CellComponent>>) {
+        let info = &self.info;
+        let efficiency = info
+            .state
+            .iter()
+            .zip(&self.state)
+            .fold(self.efficiency, |efficiency, (variable, value)| {
+                efficiency * variable.efficiency(*value)
+            });
+        let work = info.behaviour.run(&self.spec.props, efficiency, data, dt);
+        if info.state.is_empty() {
+            return (None, None);
+        }
+
+        let mut state: Vec<f32> = info
+            .state
+            .iter()
+            .zip(&self.state)
+            .map(|(variable, value)| variable.advance(*value, work, dt))
+            .collect();
+        let mut replicated = None;
+        if let Some(replication) = &info.replication {
+            let ready = state
+                .get(replication.state)
+                .is_some_and(|value| *value >= replication.threshold);
+            if ready && copies < replication.max_copies && data.base.atp >= replication.atp_cost {
+                data.base.atp -= replication.atp_cost;
+                state[replication.state] = info.state[replication.state].initial;
+                replicated = Some(vec![CellComponent::new(info.clone(), self.spec.clone())]);
+            }
+        }
+
+        ((state != self.state).then_some(state), replicated)
+    }
 }
-unsafe impl Send for CellComponent {}
-unsafe impl Sync for CellComponent {}
+
+// Cells are updated in parallel, so everything a component holds, including any state, must be
+// safe to share between threads.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<CellComponent>();
+};
@@ -1,5 +1,9 @@
 use bevy::log;
+use serde::{Deserialize, Serialize};
 
+use super::cell_base::SIGNAL_PROTEINS;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CellInternals {
     pub signal_proteins: Vec<SignalProtein>,
     pub atp: f32,
@@ -12,14 +16,8 @@ pub struct CellInternals {
 
 impl Default for CellInternals {
     fn default() -> Self {
-        let mut singal_proteins = Vec::with_capacity(rand::random::<usize>() % 100);
-        for _ in 0..singal_proteins.capacity() {
-            singal_proteins.push(SignalProtein {
-                amount: rand::random::<f32>() * 100.,
-            });
-        }
         Self {
-            signal_proteins: Vec::new(),
+            signal_proteins: vec![SignalProtein::new(0.); SIGNAL_PROTEINS],
             polysaccharides: Vec::new(),
             atp: 5.,
             glucose: 1.,
@@ -32,11 +30,20 @@ impl Default for CellInternals {
 
 /// [SignalProtein]s are signals passed to [super::cell_base::CellComponent]s. Whether or not these
 /// are used is up to the component.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SignalProtein {
     amount: f32,
 }
 
 impl SignalProtein {
+    pub fn new(amount: f32) -> Self {
+        Self { amount }
+    }
+
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
     pub fn strength(&self, cell_size: f32) -> f32 {
         self.amount / cell_size
     }
@@ -48,6 +55,7 @@ impl SignalProtein {
 /// All saccharides are hydrophilic, thus both [Polysaccharide]s and glucose will increase the size
 /// of the cell significantly, but because there is "more saccharides" in this than glucose, the
 /// relative effect per unit of energy is less.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Polysaccharide {
     pub complexity: f32,
     pub amount: f32,
@@ -60,6 +68,78 @@ const AMINO_ACID_SIZE: f32 = 0.1;
 const SIGNAL_PROTEIN_SIZE: f32 = 0.1;
 
 impl CellInternals {
+    /// Splits off `ratio` of everything in the cell, keeping the rest.
+    pub fn split_off(&mut self, ratio: f32) -> CellInternals {
+        let ratio = ratio.clamp(0., 1.);
+        let keep = 1. - ratio;
+        let split = |amount: &mut f32| {
+            let taken = *amount * ratio;
+            *amount *= keep;
+            taken
+        };
+
+        CellInternals {
+            atp: split(&mut self.atp),
+            glucose: split(&mut self.glucose),
+            proteins: split(&mut self.proteins),
+            nucleotides: split(&mut self.nucleotides),
+            amino_acids: split(&mut self.amino_acids),
+            polysaccharides: self
+                .polysaccharides
+                .iter_mut()
+                .map(|polysaccharide| Polysaccharide {
+                    complexity: polysaccharide.complexity,
+                    amount: split(&mut polysaccharide.amount),
+                })
+                .collect(),
+            signal_proteins: self
+                .signal_proteins
+                .iter_mut()
+                .map(|signal_protein| SignalProtein::new(split(&mut signal_protein.amount)))
+                .collect(),
+        }
+    }
+
+    /// Adds `fraction` of the contents of `other`. Polysaccharides of the same complexity are
+    /// merged.
+    pub fn absorb(&mut self, other: &CellInternals, fraction: f32) {
+        self.atp += other.atp * fraction;
+        self.glucose += other.glucose * fraction;
+        self.proteins += other.proteins * fraction;
+        self.nucleotides += other.nucleotides * fraction;
+        self.amino_acids += other.amino_acids * fraction;
+        for polysaccharide in &other.polysaccharides {
+            let amount = polysaccharide.amount * fraction;
+            match self
+                .polysaccharides
+                .iter_mut()
+                .find(|own| own.complexity == polysaccharide.complexity)
+            {
+                Some(own) => own.amount += amount,
+                None => self.polysaccharides.push(Polysaccharide {
+                    complexity: polysaccharide.complexity,
+                    amount,
+                }),
+            }
+        }
+        if self.signal_proteins.len() < other.signal_proteins.len() {
+            self.signal_proteins
+                .resize(other.signal_proteins.len(), SignalProtein::new(0.));
+        }
+        for (own, signal_protein) in self.signal_proteins.iter_mut().zip(&other.signal_proteins) {
+            own.amount += signal_protein.amount * fraction;
+        }
+    }
+
+    /// Sets the amount of the signal protein at `index`, adding empty ones before it if needed.
+    pub fn set_signal(&mut self, index: usize, amount: f32) {
+        if self.signal_proteins.len() <= index {
+            self.signal_proteins
+                .resize(index + 1, SignalProtein::new(0.));
+        }
+        self.signal_proteins[index].amount = amount;
+    }
+
     pub fn size(&self) -> f32 {
         let mut size = 0.1;
         for polysaccharide in &self.polysaccharides {
@@ -1,217 +1,265 @@
-use std::sync::Arc;
-
-use super::cell_base::{Cell, CellComponentType, CellData};
-use super::cell_components::CellComponent;
-use super::cell_internals::{Polysaccharide, SignalProtein};
+use std::borrow::Cow;
+
+use super::behaviour::{
+    Behaviour, EfficiencyCurve, Reaction, Reagent, Substance, POLYSACCHARIDE_COMPLEXITY,
+};
+use super::cell_base::{Cell, REGULATORY_SIGNALS, SECRETED_SIGNALS, SENSE_CHANNELS};
+use super::cell_internals::{CellInternals, SignalProtein};
+use super::component_state::{Replication, StateEffect, StateVariable};
+use super::lineage::LineageRecord;
+use super::registry::{ComponentId, ComponentInfo, ComponentKind, ComponentRegistry};
 use super::rna::{build_rna, RNA};
+use super::rng::CellRng;
 use super::weights::WeightList;
 use bevy::{log, prelude::*};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-fn get_speed_efficiency(size: f32, proteins: f32) -> (f32, f32) {
-    // The idea here is that there will be a process that will require a set amount of proteins to
-    // construct and will require a set amount of space. proteins/size is the amount of proteins in
-    // the process, assuming one unit of size is one process.
-    let ratio = proteins / size;
-
-    let efficiency = f32::tanh(ratio) * 0.9;
-    let speed = size;
-
-    (speed, efficiency)
-}
-
-pub fn flagella_builder(props: ComponentBuilderProps) -> CellComponentType {
-    let (speed, efficiency) = get_speed_efficiency(props.size, props.proteins);
-
-    CellComponentType::Membrane(CellComponent {
-        size: props.size,
-        run: Arc::new(move |cell: &mut CellData, dt: f32| {
-            let amount_weight =
-                props
-                    .weightlist
-                    .get_split_vals(cell.size, &cell.base.signal_proteins, 1)[0];
-            let mut amount = amount_weight * dt * speed;
-
-            if cell.base.atp < amount * amount {
-                amount = cell.base.atp / amount;
-            }
-
-            cell.base.atp -= amount * amount * cell.base.size() / 200.;
-            cell.base.glucose += amount / 50.;
-
-            let direction = rand::random::<f32>();
-            let negative = rand::random::<bool>();
-            if negative {
-                amount = -amount;
-            }
-            cell.velocity += Vec2 {
-                x: amount * efficiency * direction,
-                y: amount * efficiency * (1. - direction),
-            };
-
-            (None, None)
-        }),
-    })
-}
-
-pub fn reduce_polysaccharides_builder(props: ComponentBuilderProps) -> CellComponentType {
-    let (speed, efficiency) = get_speed_efficiency(props.size, props.proteins);
-
-    CellComponentType::Internal(CellComponent {
-        size: props.size,
-        run: Arc::new(move |cell: &mut CellData, dt: f32| {
-            if let Some(polysaccharide) = cell.base.polysaccharides.get_mut(0) {
-                let amount_weight =
-                    props
-                        .weightlist
-                        .get_split_vals(cell.size, &cell.base.signal_proteins, 1)[0];
-                let amount = amount_weight * dt * speed;
-                if amount < polysaccharide.amount {
-                    polysaccharide.amount -= amount;
-                    cell.base.glucose += amount * polysaccharide.complexity * efficiency;
-                } else {
-                    cell.base.glucose += polysaccharide.amount * efficiency;
-                    cell.base.polysaccharides.remove(0);
-                }
-            }
-
-            (None, None)
-        }),
-    })
-}
-
-const AMINO_ACID_FROM_GLYCOLYSIS: f32 = 0.1;
-pub fn burn_glucose_builder(props: ComponentBuilderProps) -> CellComponentType {
-    let (speed, efficiency) = get_speed_efficiency(props.size, props.proteins);
-
-    CellComponentType::Internal(CellComponent {
-        size: props.size,
-        run: Arc::new(move |cell: &mut CellData, dt: f32| {
-            let amount_weight =
-                props
-                    .weightlist
-                    .get_split_vals(cell.size, &cell.base.signal_proteins, 1)[0];
-            let mut amount = amount_weight * dt * speed;
-
-            if cell.base.glucose < amount {
-                amount = cell.base.glucose;
-            }
-
-            cell.base.glucose -= amount;
-            cell.base.atp += amount * efficiency;
-            cell.base.amino_acids += amount * AMINO_ACID_FROM_GLYCOLYSIS;
-
-            (None, None)
-        }),
-    })
-}
-
-const POLYSACCHARIDE_ATP_COST: f32 = 0.01;
-pub fn create_polysaccharides_builder(props: ComponentBuilderProps) -> CellComponentType {
-    let (speed, efficiency) = get_speed_efficiency(props.size, props.proteins);
-
-    CellComponentType::Internal(CellComponent {
-        size: props.size,
-        run: Arc::new(move |cell: &mut CellData, dt: f32| {
-            let amount_weight =
-                props
-                    .weightlist
-                    .get_split_vals(cell.size, &cell.base.signal_proteins, 1)[0];
-            let mut amount = amount_weight * dt * speed;
-
-            if cell.base.glucose < amount {
-                amount = cell.base.glucose;
-            }
-            if cell.base.atp < amount * POLYSACCHARIDE_ATP_COST {
-                amount = cell.base.atp / POLYSACCHARIDE_ATP_COST;
-            }
-
-            cell.base.atp -= amount * POLYSACCHARIDE_ATP_COST;
-            cell.base.glucose -= amount;
-            cell.base.polysaccharides.push(Polysaccharide {
-                complexity: 2.,
-                amount: amount * efficiency,
-            });
-
-            (None, None)
-        }),
-    })
-}
-
-pub fn create_proteins_builder(props: ComponentBuilderProps) -> CellComponentType {
-    let (speed, efficiency) = get_speed_efficiency(props.size, props.proteins);
-
-    CellComponentType::Internal(CellComponent {
-        size: props.size,
-        run: Arc::new(move |cell: &mut CellData, dt: f32| {
-            let amount_weight =
-                props
-                    .weightlist
-                    .get_split_vals(cell.size, &cell.base.signal_proteins, 1)[0];
-            let mut amount = amount_weight * dt * speed;
-
-            if cell.base.amino_acids < amount {
-                amount = cell.base.amino_acids;
-            }
-
-            cell.base.amino_acids -= amount;
-            cell.base.proteins += amount * efficiency;
-
-            (None, None)
-        }),
-    })
+/// Registers the components every cell can be built from. See [ComponentRegistry::default].
+pub fn register_core_components(registry: &mut ComponentRegistry) {
+    for info in core_components() {
+        registry.register(info);
+    }
 }
 
-pub fn create_cell_builder(props: ComponentBuilderProps) -> CellComponentType {
-    CellComponentType::Internal(CellComponent {
-        size: 1.,
-        run: Arc::new(move |data: &mut CellData, dt: f32| {
-            if data.base.atp >= 15. {
-                data.base.atp -= 10.;
-                let new_cell = create_cell(data.rna.clone());
-                data.new_cells.push(new_cell);
+/// Definitions of the components every cell can be built from.
+pub fn core_components() -> Vec<ComponentInfo> {
+    use Substance::*;
+
+    let reaction =
+        |id, name, kind, inputs: &[Reagent], outputs: &[Reagent], byproducts: &[Reagent]| {
+            ComponentInfo {
+                id: ComponentId::new(id),
+                name: Cow::Borrowed(name),
+                kind,
+                efficiency: EfficiencyCurve::default(),
+                size: None,
+                behaviour: Behaviour::Reaction(Reaction {
+                    inputs: inputs.to_vec(),
+                    outputs: outputs.to_vec(),
+                    byproducts: byproducts.to_vec(),
+                    rate: 1.,
+                }),
+                state: Vec::new(),
+                replication: None,
             }
+        };
+    let special = |id, name, kind, size, behaviour| ComponentInfo {
+        id: ComponentId::new(id),
+        name: Cow::Borrowed(name),
+        kind,
+        efficiency: EfficiencyCurve::default(),
+        size,
+        behaviour,
+        state: Vec::new(),
+        replication: None,
+    };
 
-            (None, None)
-        }),
-    })
-}
-
-pub fn register_component_builders() -> Vec<fn(ComponentBuilderProps) -> CellComponentType> {
     vec![
-        create_cell_builder,
-        flagella_builder,
-        burn_glucose_builder,
-        create_polysaccharides_builder,
-        create_proteins_builder,
-        reduce_polysaccharides_builder,
+        special(
+            "create_cell",
+            "Cell division",
+            ComponentKind::Internal,
+            Some(1.),
+            Behaviour::Division {
+                atp_threshold: 15.,
+                atp_cost: 10.,
+            },
+        ),
+        special(
+            "flagella",
+            "Flagella",
+            ComponentKind::Membrane,
+            None,
+            Behaviour::Flagella {
+                turn_rate: 0.1,
+                atp_cost: 1. / 200.,
+            },
+        ),
+        reaction(
+            "burn_glucose",
+            "Glycolysis",
+            ComponentKind::Internal,
+            &[Reagent::new(Glucose, 1.)],
+            &[Reagent::new(Atp, 1.)],
+            &[Reagent::new(AminoAcids, 0.1)],
+        ),
+        reaction(
+            "create_polysaccharides",
+            "Polysaccharide synthesis",
+            ComponentKind::Internal,
+            &[Reagent::new(Glucose, 1.), Reagent::new(Atp, 0.01)],
+            &[Reagent::new(Polysaccharides, 1.)],
+            &[],
+        ),
+        reaction(
+            "create_proteins",
+            "Ribosome",
+            ComponentKind::Internal,
+            &[Reagent::new(AminoAcids, 1.)],
+            &[Reagent::new(Proteins, 1.)],
+            &[],
+        ),
+        reaction(
+            "reduce_polysaccharides",
+            "Polysaccharide digestion",
+            ComponentKind::Internal,
+            &[Reagent::new(Polysaccharides, 1.)],
+            &[Reagent::new(Glucose, POLYSACCHARIDE_COMPLEXITY)],
+            &[],
+        ),
+        reaction(
+            "glucose_uptake",
+            "Glucose transporter",
+            ComponentKind::Membrane,
+            &[Reagent::new(ExternalGlucose, 1.)],
+            &[Reagent::new(Glucose, 1.)],
+            &[],
+        ),
+        special(
+            "receptor",
+            "Receptor",
+            ComponentKind::Membrane,
+            None,
+            Behaviour::Receptor { atp_cost: 0.01 },
+        ),
+        special(
+            "phagocytosis",
+            "Phagocytosis",
+            ComponentKind::Membrane,
+            None,
+            Behaviour::Phagocytosis { atp_cost: 0.02 },
+        ),
+        special(
+            "gene_regulator",
+            "Gene regulator",
+            ComponentKind::Internal,
+            None,
+            Behaviour::Regulator {
+                first: SENSE_CHANNELS,
+                count: REGULATORY_SIGNALS,
+                rate: 1.,
+                atp_cost: 0.1,
+            },
+        ),
+        special(
+            "signal_secretion",
+            "Signal secretion",
+            ComponentKind::Membrane,
+            None,
+            Behaviour::Secretion {
+                first: SENSE_CHANNELS,
+                count: SECRETED_SIGNALS,
+                atp_cost: 0.05,
+            },
+        ),
+        special(
+            "signal_import",
+            "Signal importer",
+            ComponentKind::Membrane,
+            None,
+            Behaviour::SignalImport {
+                first: SENSE_CHANNELS,
+                count: SECRETED_SIGNALS,
+                rate: 1.,
+            },
+        ),
+        // Only works once mature, wears down with use and divides on its own.
+        ComponentInfo {
+            state: vec![
+                StateVariable {
+                    name: Cow::Borrowed("maturity"),
+                    initial: 0.,
+                    rate: 0.05,
+                    wear: 0.,
+                    max: Some(1.),
+                    effect: StateEffect::Enables,
+                },
+                StateVariable {
+                    name: Cow::Borrowed("damage"),
+                    initial: 0.,
+                    rate: 0.,
+                    wear: 0.01,
+                    max: Some(1.),
+                    effect: StateEffect::Impairs,
+                },
+                StateVariable {
+                    name: Cow::Borrowed("division timer"),
+                    initial: 0.,
+                    rate: 1.,
+                    wear: 0.,
+                    max: None,
+                    effect: StateEffect::None,
+                },
+            ],
+            replication: Some(Replication {
+                state: 2,
+                threshold: 20.,
+                atp_cost: 5.,
+                max_copies: 4,
+            }),
+            ..reaction(
+                "mitochondrion",
+                "Mitochondrion",
+                ComponentKind::Internal,
+                &[Reagent::new(Glucose, 1.)],
+                &[Reagent::new(Atp, 2.)],
+                &[],
+            )
+        },
     ]
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ComponentBuilderProps {
     pub size: f32,
     pub proteins: f32,
     pub weightlist: WeightList,
 }
 
-impl Default for ComponentBuilderProps {
-    fn default() -> Self {
+/// What a [CellComponent] was built from. Building a component again from its spec with
+/// [ComponentRegistry::build] gives the same component, which is how components are saved.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ComponentSpec {
+    pub id: ComponentId,
+    pub props: ComponentBuilderProps,
+}
+
+impl ComponentBuilderProps {
+    pub fn random(rng: &mut impl Rng) -> Self {
         Self {
-            size: rand::random::<f32>() * 2.,
-            proteins: rand::random::<f32>() * 2.,
-            weightlist: WeightList::default(),
+            size: rng.gen::<f32>() * 2.,
+            proteins: rng.gen::<f32>() * 2.,
+            weightlist: WeightList::random(rng),
         }
     }
 }
 
-pub fn create_cell(rna: RNA) -> Cell {
-    let components = register_component_builders();
-    let mut cell = Cell::default();
-    for (i, strand) in rna.iter().enumerate() {
-        if let Some(props) = strand {
-            cell.inject_component((components[i])(props.clone()));
+/// Builds a cell from its genes, starting out with `internals`. Genes of components that are not
+/// in `registry` are kept in the [RNA] but do nothing, so they survive being run without the crate
+/// that registered them.
+pub fn create_cell(
+    rna: RNA,
+    internals: CellInternals,
+    lineage: LineageRecord,
+    rng: CellRng,
+    registry: &ComponentRegistry,
+) -> Cell {
+    let mut cell = Cell::new(RNA::new(), lineage, rng);
+    cell.data.base = internals;
+    for (id, gene) in rna.iter() {
+        let Some(props) = gene else {
+            continue;
+        };
+        let spec = ComponentSpec {
+            id: id.clone(),
+            props: props.clone(),
+        };
+        if let Some(component) = registry.build(spec) {
+            cell.inject_component(component);
         }
     }
+    cell.data.rna = rna;
 
     cell
 }
@@ -0,0 +1,65 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+/// A value a component keeps between steps, such as a timer, damage or how mature it is.
+/// Declared in [super::ComponentInfo::state], and saved in snapshots.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StateVariable {
+    /// Human readable name, shown in the inspector.
+    pub name: Cow<'static, str>,
+    /// Value of new components.
+    #[serde(default)]
+    pub initial: f32,
+    /// Change per second.
+    #[serde(default)]
+    pub rate: f32,
+    /// Change per unit of work the component does, see [super::Behaviour::run].
+    #[serde(default)]
+    pub wear: f32,
+    /// The value is kept between zero and this. Unbounded if not set.
+    #[serde(default)]
+    pub max: Option<f32>,
+    #[serde(default)]
+    pub effect: StateEffect,
+}
+
+/// How a [StateVariable] changes how well its component works.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StateEffect {
+    #[default]
+    None,
+    /// Efficiency is scaled by `1 - value`, for instance for damage.
+    Impairs,
+    /// Efficiency is scaled by `value`, for instance for maturation.
+    Enables,
+}
+
+/// Makes a component copy itself once one of its [StateVariable]s reaches a threshold. The
+/// variable is reset on the original, and the copy starts out like a new component.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replication {
+    /// Index into [super::ComponentInfo::state].
+    pub state: usize,
+    pub threshold: f32,
+    pub atp_cost: f32,
+    /// Copies of the component a cell can have at most, including the original.
+    pub max_copies: usize,
+}
+
+impl StateVariable {
+    /// Value after `dt` of doing `work`.
+    pub fn advance(&self, value: f32, work: f32, dt: f32) -> f32 {
+        let value = value + self.rate * dt + self.wear * work;
+        value.clamp(0., self.max.unwrap_or(f32::INFINITY))
+    }
+
+    /// Factor the component's efficiency is scaled by at `value`.
+    pub fn efficiency(&self, value: f32) -> f32 {
+        match self.effect {
+            StateEffect::None => 1.,
+            StateEffect::Impairs => (1. - value).clamp(0., 1.),
+            StateEffect::Enables => value.clamp(0., 1.),
+        }
+    }
+}
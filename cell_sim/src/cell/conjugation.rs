@@ -0,0 +1,97 @@
+use bevy::{log, prelude::*};
+use bevy_rapier2d::geometry::CollidingEntities;
+use serde::{Deserialize, Serialize};
+
+use super::cell_base::Cell;
+use super::component_instances::create_cell;
+use super::crossover::crossover_rna;
+use super::ctl::{offspring_position, spawn_cell};
+use super::lineage::{sort_contacts, Lineage};
+use super::mutation::{mutate_rna, MutationRates};
+use super::registry::ComponentRegistry;
+use super::rng::derive_rng;
+use crate::timing::SimClock;
+
+/// How cells pass on their [super::rna::RNA] once they divide.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReproductionMode {
+    /// Offspring are spawned as soon as a cell divides, with a mutated copy of the parent's RNA.
+    #[default]
+    Asexual,
+    /// Offspring are held by the cell until it touches another cell which is also holding one.
+    /// The two are then recombined with [crossover_rna] and each parent gets a daughter.
+    Sexual,
+}
+
+/// Cells can only hold this many offspring while waiting for a mate, the rest are reabsorbed.
+pub const MAX_PENDING_OFFSPRING: usize = 1;
+
+/// Mates touching cells when running in [ReproductionMode::Sexual]. Goes through every contact,
+/// not only new ones, so cells that were already touching when they divided can still mate.
+#[allow(clippy::too_many_arguments)]
+pub fn conjugate_cells(
+    mut commands: Commands,
+    touching: Query<(Entity, &CollidingEntities), With<Cell>>,
+    mut cells: Query<(&mut Cell, &Transform)>,
+    mode: Res<ReproductionMode>,
+    mutation_rates: Res<MutationRates>,
+    registry: Res<ComponentRegistry>,
+    mut lineage: ResMut<Lineage>,
+    clock: Res<SimClock>,
+) {
+    if *mode != ReproductionMode::Sexual {
+        return;
+    }
+
+    let touching = touching
+        .iter()
+        .flat_map(|(a, colliding)| colliding.iter().map(move |b| (a, b)));
+    let contacts = sort_contacts(touching, |entity| {
+        cells.get(entity).ok().map(|(cell, _)| cell.data.lineage.id)
+    });
+    for (a, b) in contacts {
+        let Ok([(mut cell_a, transform_a), (mut cell_b, transform_b)]) = cells.get_many_mut([a, b])
+        else {
+            continue;
+        };
+        if cell_a.data.offspring.is_empty() || cell_b.data.offspring.is_empty() {
+            continue;
+        }
+        let offspring_a = cell_a.data.offspring.pop().unwrap();
+        let offspring_b = cell_b.data.offspring.pop().unwrap();
+        let (rna_a, rna_b) = (offspring_a.rna.clone(), offspring_b.rna.clone());
+        let parents = [cell_a.data.lineage.clone(), cell_b.data.lineage.clone()];
+
+        log::info!("Cells {:?} and {:?} mated", a, b);
+        // Each daughter is drawn from the stream of the parent it is placed next to.
+        // Each daughter also takes the internals its own parent split off.
+        for (i, ((mut cell, transform), offspring)) in [
+            ((cell_a, transform_a), offspring_a),
+            ((cell_b, transform_b), offspring_b),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let size = cell.size();
+            let rng = &mut cell.data.rng;
+            let rna = mutate_rna(
+                &crossover_rna(&rna_a, &rna_b, rng),
+                &mutation_rates,
+                &registry,
+                rng,
+            );
+            let position = offspring_position(transform.translation, size, i, rng);
+            spawn_cell(
+                create_cell(
+                    rna,
+                    offspring.internals,
+                    lineage.birth(Some(&parents[i]), Some(&parents[1 - i]), clock.elapsed),
+                    derive_rng(rng),
+                    &registry,
+                ),
+                &mut commands,
+                position,
+            );
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeSet;
+
+use rand::Rng;
+
+use super::component_instances::ComponentBuilderProps;
+use super::rna::RNA;
+use super::weights::WeightList;
+
+/// Recombines the [RNA] of two parents. Each gene is taken from either parent at random, and
+/// genes active in both parents are recombined with [crossover_props].
+pub fn crossover_rna(a: &RNA, b: &RNA, rng: &mut impl Rng) -> RNA {
+    let ids: BTreeSet<_> = a.keys().chain(b.keys()).collect();
+    ids.into_iter()
+        .map(|id| {
+            let gene = match (a.get(id).cloned().flatten(), b.get(id).cloned().flatten()) {
+                (Some(a), Some(b)) => Some(crossover_props(&a, &b, rng)),
+                (a, b) => match rng.gen::<bool>() {
+                    true => a,
+                    false => b,
+                },
+            };
+            (id.clone(), gene)
+        })
+        .collect()
+}
+
+pub fn crossover_props(
+    a: &ComponentBuilderProps,
+    b: &ComponentBuilderProps,
+    rng: &mut impl Rng,
+) -> ComponentBuilderProps {
+    let (size, proteins) = match rng.gen::<bool>() {
+        true => (a.size, a.proteins),
+        false => (b.size, b.proteins),
+    };
+
+    ComponentBuilderProps {
+        size,
+        proteins,
+        weightlist: crossover_weightlist(&a.weightlist, &b.weightlist, rng),
+    }
+}
+
+/// Single point crossover of two [WeightList]s. As the lists are sorted by
+/// [super::weights::Weight::index], the crossover point is chosen on the index axis so that both
+/// halves refer to the same region of the list.
+pub fn crossover_weightlist(a: &WeightList, b: &WeightList, rng: &mut impl Rng) -> WeightList {
+    let (a, b) = match rng.gen::<bool>() {
+        true => (a.get(), b.get()),
+        false => (b.get(), a.get()),
+    };
+
+    // Lists are never empty, see [WeightList::new].
+    let start = a[0].index.min(b[0].index);
+    let end = a[a.len() - 1].index.max(b[b.len() - 1].index);
+    let point = start + rng.gen::<f32>() * (end - start);
+
+    let weights: Vec<_> = a
+        .iter()
+        .filter(|w| w.index < point)
+        .chain(b.iter().filter(|w| w.index >= point))
+        .copied()
+        .collect();
+
+    match weights.is_empty() {
+        true => WeightList::new(a.clone()),
+        false => WeightList::new(weights),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::super::registry::ComponentId;
+    use super::super::weights::{Sensitivity, Weight};
+    use super::*;
+
+    const SHARED: ComponentId = ComponentId::new("shared");
+    const ONLY_A: ComponentId = ComponentId::new("only_a");
+    const ONLY_B: ComponentId = ComponentId::new("only_b");
+    const OFF_IN_A: ComponentId = ComponentId::new("off_in_a");
+
+    /// A gene whose every value is `tag`, so the parent it came from can be told apart.
+    fn gene(tag: f32) -> Option<ComponentBuilderProps> {
+        let weights = (0..20)
+            .map(|i| Weight {
+                index: i as f32 * 5. + tag / 10.,
+                range: 1.,
+                base: tag,
+                sensitivity: Sensitivity {
+                    index: 0,
+                    weight: tag,
+                },
+            })
+            .collect();
+        Some(ComponentBuilderProps {
+            size: tag,
+            proteins: tag,
+            weightlist: WeightList::new(weights),
+        })
+    }
+
+    fn parents() -> (RNA, RNA) {
+        let a = RNA::from([(SHARED, gene(1.)), (ONLY_A, gene(3.)), (OFF_IN_A, None)]);
+        let b = RNA::from([(SHARED, gene(2.)), (ONLY_B, gene(4.)), (OFF_IN_A, gene(5.))]);
+        (a, b)
+    }
+
+    fn tags(gene: &Option<ComponentBuilderProps>) -> Vec<f32> {
+        gene.iter()
+            .flat_map(|props| {
+                let weights = props.weightlist.get().iter();
+                [props.size, props.proteins]
+                    .into_iter()
+                    .chain(weights.flat_map(|w| [w.base, w.sensitivity.weight]))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn genes_line_up_by_component_id() {
+        let (a, b) = parents();
+        for seed in 0..20 {
+            let child = crossover_rna(&a, &b, &mut ChaCha8Rng::seed_from_u64(seed));
+            assert_eq!(
+                child.keys().collect::<Vec<_>>(),
+                [&OFF_IN_A, &ONLY_A, &ONLY_B, &SHARED]
+            );
+            // A gene only one parent has is either inherited whole or left out.
+            for (id, tag) in [(&ONLY_A, 3.), (&ONLY_B, 4.), (&OFF_IN_A, 5.)] {
+                assert!(child[id].is_none() || tags(&child[id]).iter().all(|t| *t == tag));
+            }
+            // Genes both parents have are recombined, never dropped.
+            assert!(child[&SHARED].is_some());
+        }
+    }
+
+    #[test]
+    fn child_only_contains_genes_from_its_parents() {
+        let (a, b) = parents();
+        for seed in 0..20 {
+            let child = crossover_rna(&a, &b, &mut ChaCha8Rng::seed_from_u64(seed));
+            let shared = child[&SHARED].as_ref().unwrap();
+            assert!(shared.size == shared.proteins && [1., 2.].contains(&shared.size));
+            assert!(tags(&child[&SHARED]).iter().all(|t| [1., 2.].contains(t)));
+            for weight in shared.weightlist.get() {
+                let parent = match weight.base == 1. {
+                    true => &a[&SHARED],
+                    false => &b[&SHARED],
+                };
+                let parent = parent.as_ref().unwrap().weightlist.get();
+                assert!(parent.iter().any(|w| w.index == weight.index));
+            }
+        }
+    }
+
+    #[test]
+    fn crossover_is_deterministic() {
+        let (a, b) = parents();
+        let child = |seed| {
+            let child = crossover_rna(&a, &b, &mut ChaCha8Rng::seed_from_u64(seed));
+            ron::to_string(&child).unwrap()
+        };
+        assert_eq!(child(3), child(3));
+    }
+}
@@ -1,129 +1,229 @@
 use std::sync::Arc;
 
 use super::cell_base::{Cell, CellComponentType, CellData};
-use super::cell_bundle::{update_cell_mesh, update_cell_physics, CellBundle};
-use super::cell_components::CellComponent;
-use super::component_instances::{
-    burn_glucose_builder, create_cell, create_cell_builder, flagella_builder, ComponentBuilderProps,
+use super::cell_bundle::{
+    update_cell_mesh, update_cell_physics, CellBundle, CellMesh, CellMeshBundle, CellRenderAssets,
 };
+use super::cell_components::CellComponent;
+use super::cell_internals::CellInternals;
+use super::component_instances::{create_cell, ComponentBuilderProps};
+use super::conjugation::{ReproductionMode, MAX_PENDING_OFFSPRING};
+use super::genome::SeedGenomes;
+use super::lineage::Lineage;
+use super::mutation::{mutate_rna, MutationRates};
+use super::registry::ComponentRegistry;
 use super::rna::build_rna;
+use super::rng::{derive_rng, SimRng};
 use super::weights::WeightList;
+use crate::scene::WorldBounds;
+use crate::timing::{SimClock, SimTiming};
+use crate::CellSimSettings;
 use bevy::log;
-use bevy::window::PrimaryWindow;
-use bevy::{prelude::*, sprite::Mesh2dHandle};
+use bevy::prelude::*;
 use bevy_rapier2d::dynamics::{Damping, Velocity};
 use bevy_rapier2d::geometry::{Collider, ColliderMassProperties};
+use rand::Rng;
+
+/// Starts each cell from where physics left it, as collisions change how cells move.
+pub fn read_cell_physics(mut cells: Query<(&mut Cell, &Velocity, &Transform)>) {
+    cells
+        .par_iter_mut()
+        .for_each(|(mut cell, velocity, transform)| {
+            cell.data.velocity = velocity.linvel;
+            cell.data.angular_velocity = velocity.angvel;
+            cell.data.heading = transform.local_x().truncate().normalize_or_zero();
+        });
+}
 
-type CellZip<'a> = (
-    Entity,
-    &'a mut Cell,
-    &'a mut Collider,
-    &'a mut Velocity,
-    &'a mut Mesh2dHandle,
-    &'a mut Handle<ColorMaterial>,
-    &'a mut ColliderMassProperties,
-    &'a mut Damping,
-    &'a Transform,
-);
+/// Runs the components of every living cell for each step of [SimTiming].
+pub fn metabolize_cells(mut cells: Query<&mut Cell>, timing: Res<SimTiming>) {
+    log::info!("cells: {}", cells.iter().len());
+    let (steps, dt) = timing.steps();
+    cells
+        .par_iter_mut()
+        .for_each(|mut cell| cell.metabolize(steps, dt));
+}
 
-pub fn update_all_cells(
+/// Spawns the offspring cells made this step, or holds them for a mate depending on
+/// [ReproductionMode].
+pub fn reproduce_cells(
     mut commands: Commands,
-    mut cell_zip: Query<CellZip>,
-    time: Res<Time>,
-    mut mesh_assets: ResMut<Assets<Mesh>>,
-    mut color_assets: ResMut<Assets<ColorMaterial>>,
+    mut cells: Query<(Entity, &mut Cell, &Transform)>,
+    mutation_rates: Res<MutationRates>,
+    reproduction_mode: Res<ReproductionMode>,
+    registry: Res<ComponentRegistry>,
+    mut lineage: ResMut<Lineage>,
+    clock: Res<SimClock>,
 ) {
-    log::info!("cells: {}", cell_zip.iter().len());
-    for (
-        entity,
-        mut cell,
-        mut collider,
-        mut velocity,
-        mut mesh,
-        mut color,
-        mut collider_mass_properties,
-        mut damping,
-        transform,
-    ) in cell_zip.iter_mut()
-    {
+    for (entity, mut cell, transform) in cells.iter_mut() {
+        let size = cell.size();
+        let data = &mut cell.data;
+        match *reproduction_mode {
+            ReproductionMode::Asexual => {
+                let parent = data.lineage.clone();
+                data.offspring
+                    .drain(..)
+                    .enumerate()
+                    .for_each(|(i, offspring)| {
+                        log::info!("Cell {:?} spawned", entity);
+                        let rna =
+                            mutate_rna(&offspring.rna, &mutation_rates, &registry, &mut data.rng);
+                        let position =
+                            offspring_position(transform.translation, size, i, &mut data.rng);
+                        spawn_cell(
+                            create_cell(
+                                rna,
+                                offspring.internals,
+                                lineage.birth(Some(&parent), None, clock.elapsed),
+                                derive_rng(&mut data.rng),
+                                &registry,
+                            ),
+                            &mut commands,
+                            position,
+                        );
+                    })
+            }
+            // Offspring wait for a mate, see [super::conjugation::conjugate_cells]. Cells do not
+            // divide while holding one, so this only reabsorbs offspring beyond
+            // [MAX_PENDING_OFFSPRING] from older snapshots. The ATP paid for dividing is not
+            // refunded.
+            ReproductionMode::Sexual => {
+                let excess = data.offspring.len().saturating_sub(MAX_PENDING_OFFSPRING);
+                for offspring in data.offspring.drain(..excess) {
+                    data.base.absorb(&offspring.internals, 1.);
+                }
+            }
+        }
+    }
+}
+
+/// Despawns cells that ran out of ATP.
+pub fn remove_dead_cells(
+    mut commands: Commands,
+    mut cells: Query<(Entity, &mut Cell)>,
+    mut lineage: ResMut<Lineage>,
+    clock: Res<SimClock>,
+) {
+    for (entity, mut cell) in cells.iter_mut() {
         if cell.data.base.atp <= 0.1 {
-            commands.entity(entity).despawn();
+            cell.data.reabsorb_offspring();
+            commands.entity(entity).despawn_recursive();
+            lineage.death(cell.data.lineage.id, clock.elapsed);
             log::info!("Cell {:?} died", entity);
         }
-        let size = cell.size();
-        cell.data
-            .new_cells
-            .drain(..)
-            .enumerate()
-            .for_each(|(i, new_cell)| {
-                let offset =
-                    Vec3::new(rand::random::<f32>() - 0.5, rand::random::<f32>() - 0.5, 0.)
-                        .normalize()
-                        * f32::sqrt(size)
-                        * (i + 1) as f32;
-                log::info!("Cell {:?} spawned", entity);
-                spawn_cell(
-                    new_cell,
-                    &mut commands,
-                    &mut color_assets,
-                    &mut mesh_assets,
-                    transform.translation + offset,
-                );
-            });
-        update_cell_mesh(
-            &mut cell,
-            &mut mesh,
-            &mut color,
-            &mut mesh_assets,
-            &mut color_assets,
-        );
+    }
+}
+
+/// Hands the size and movement of every cell to physics.
+pub fn write_cell_physics(
+    mut cells: Query<(
+        &Cell,
+        &mut Collider,
+        &mut Velocity,
+        &mut ColliderMassProperties,
+        &mut Damping,
+    )>,
+) {
+    for (cell, mut collider, mut velocity, mut collider_mass_properties, mut damping) in
+        cells.iter_mut()
+    {
         update_cell_physics(
-            &cell,
+            cell,
             &mut collider,
             &mut collider_mass_properties,
             &mut damping,
-            &mut velocity,
         );
+        velocity.linvel = cell.data.velocity;
+        velocity.angvel = cell.data.angular_velocity;
+    }
+}
+
+/// Position of the `i`th daughter of a cell at `parent`, so daughters don't spawn on top of each
+/// other.
+pub(super) fn offspring_position(parent: Vec3, size: f32, i: usize, rng: &mut impl Rng) -> Vec3 {
+    let offset = Vec3::new(rng.gen::<f32>() - 0.5, rng.gen::<f32>() - 0.5, 0.).normalize()
+        * f32::sqrt(size)
+        * (i + 1) as f32;
+
+    parent + offset
+}
+
+pub(super) fn spawn_cell(cell: Cell, commands: &mut Commands, position: Vec3) {
+    commands.spawn((CellBundle::new(cell, position),));
+}
+
+/// Gives newly spawned cells a mesh. Only needed when rendering.
+pub fn attach_cell_meshes(
+    mut commands: Commands,
+    cells: Query<(Entity, &Cell), Without<Visibility>>,
+    assets: Res<CellRenderAssets>,
+) {
+    for (entity, cell) in cells.iter() {
+        commands
+            .entity(entity)
+            .insert(VisibilityBundle::default())
+            .with_children(|parent| {
+                parent.spawn(CellMeshBundle::new(&assets, cell));
+            });
     }
-    let a = time.delta().as_millis();
-    cell_zip.par_iter_mut().for_each(|(_, mut cell, ..)| {
-        for _ in 0..(a) {
-            if cell.data.base.atp <= 0.1 {
-                break;
+}
+
+pub fn update_cell_meshes(
+    cells: Query<(&Cell, &Children)>,
+    mut meshes: Query<(&mut Transform, &mut Handle<ColorMaterial>), With<CellMesh>>,
+    assets: Res<CellRenderAssets>,
+) {
+    for (cell, children) in cells.iter() {
+        for &child in children.iter() {
+            if let Ok((mut transform, mut material)) = meshes.get_mut(child) {
+                update_cell_mesh(cell, &mut transform, &mut material, &assets);
             }
-            cell.update(0.01);
         }
-    });
+    }
 }
 
-fn spawn_cell(
-    cell: Cell,
-    commands: &mut Commands,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    position: Vec3,
-) {
-    commands.spawn((CellBundle::new(meshes, materials, cell, position),));
+/// Run condition for [spawn_cells], so that no new cells are spawned when a snapshot was
+/// restored.
+pub fn no_cells(cells: Query<(), With<Cell>>) -> bool {
+    cells.is_empty()
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_cells(
     mut commands: Commands,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    settings: Res<CellSimSettings>,
+    bounds: Res<WorldBounds>,
+    seed_genomes: Res<SeedGenomes>,
+    mut sim_rng: ResMut<SimRng>,
+    registry: Res<ComponentRegistry>,
+    mut lineage: ResMut<Lineage>,
+    clock: Res<SimClock>,
 ) {
-    let window = window_query.single();
-    (0..500).enumerate().for_each(|_| {
+    (0..settings.spawn_count).enumerate().for_each(|(i, _)| {
+        let rna = match seed_genomes.0.is_empty() {
+            true => build_rna(
+                &WeightList::random(&mut sim_rng.rng),
+                1.,
+                &Vec::new(),
+                &registry,
+            ),
+            false => seed_genomes.0[i % seed_genomes.0.len()].clone(),
+        };
+        let position = Vec3::new(
+            sim_rng.rng.gen::<f32>() * bounds.width,
+            sim_rng.rng.gen::<f32>() * bounds.height,
+            0.,
+        );
         spawn_cell(
-            create_cell(build_rna(&WeightList::default(), 1., &Vec::new())),
-            &mut commands,
-            &mut materials,
-            &mut meshes,
-            Vec3::new(
-                rand::random::<f32>() * window.width(),
-                rand::random::<f32>() * window.height(),
-                0.,
+            create_cell(
+                rna,
+                CellInternals::default(),
+                lineage.birth(None, None, clock.elapsed),
+                sim_rng.cell_rng(),
+                &registry,
             ),
+            &mut commands,
+            position,
         )
     });
 }
@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use bevy::{log, prelude::*};
+
+use serde::{Deserialize, Serialize};
+
+use super::cell_base::Cell;
+use super::registry::{validate_components, ComponentInfo, ComponentRegistry};
+use super::rna::RNA;
+use super::storage::{self, FileKind, StorageError};
+
+/// On-disk genome format. Bump the version whenever the layout of [Genome] changes. Genes are
+/// keyed by [super::registry::ComponentId], so registering new components does not need a new
+/// version.
+pub const GENOME_FILE: FileKind = FileKind {
+    magic: b"CSGN",
+    version: 3,
+};
+
+/// A saved genome, along with the definitions of the components it has genes for, so it can be
+/// loaded into a run that does not have them registered.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Genome {
+    pub rna: RNA,
+    pub components: Vec<ComponentInfo>,
+}
+
+/// Directory genomes archived during a run are written to.
+pub const GENOME_DIR: &str = "genomes";
+
+/// Saves a genome as RON, or as compact binary if the path ends in `.bin`.
+pub fn save_genome(
+    path: &Path,
+    rna: &RNA,
+    registry: &ComponentRegistry,
+) -> Result<(), StorageError> {
+    let genome = Genome {
+        rna: rna.clone(),
+        components: registry.definitions(rna.keys()),
+    };
+    storage::save(path, &GENOME_FILE, &genome)
+}
+
+/// Register [Genome::components] with [ComponentRegistry::merge] before building cells from it.
+pub fn load_genome(path: &Path) -> Result<Genome, StorageError> {
+    let genome: Genome = storage::load(path, &GENOME_FILE)?;
+    validate_components(&genome.components)?;
+    Ok(genome)
+}
+
+/// Genomes new runs are seeded from instead of random [RNA]. See [super::ctl::spawn_cells].
+#[derive(Resource, Default)]
+pub struct SeedGenomes(pub Vec<RNA>);
+
+/// Saves the genome of the cell with the most ATP to [GENOME_DIR] when G is pressed.
+pub fn archive_genome(
+    keys: Res<Input<KeyCode>>,
+    cells: Query<(Entity, &Cell)>,
+    registry: Res<ComponentRegistry>,
+) {
+    if !keys.just_pressed(KeyCode::G) {
+        return;
+    }
+    let Some((entity, cell)) = cells
+        .iter()
+        .max_by(|(_, a), (_, b)| a.data.base.atp.total_cmp(&b.data.base.atp))
+    else {
+        return;
+    };
+
+    let path =
+        Path::new(GENOME_DIR).join(format!("{}v{}.ron", entity.index(), entity.generation()));
+    let result = std::fs::create_dir_all(GENOME_DIR)
+        .map_err(StorageError::from)
+        .and_then(|_| save_genome(&path, &cell.data.rna, &registry));
+    match result {
+        Ok(()) => log::info!("Saved genome of cell {:?} to {}", entity, path.display()),
+        Err(e) => log::error!("Could not save genome of cell {:?}: {}", entity, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::cell::component_instances::ComponentBuilderProps;
+    use crate::cell::registry::ComponentId;
+
+    #[test]
+    fn round_trips_in_both_formats() {
+        let registry = ComponentRegistry::default();
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let mut rna = RNA::new();
+        rna.insert(
+            ComponentId::new("flagella"),
+            Some(ComponentBuilderProps::random(&mut rng)),
+        );
+        rna.insert(ComponentId::new("burn_glucose"), None);
+
+        for extension in ["ron", "bin"] {
+            let path = std::env::temp_dir().join(format!(
+                "cell_sim_genome_{}.{}",
+                std::process::id(),
+                extension
+            ));
+            save_genome(&path, &rna, &registry).unwrap();
+            let genome = load_genome(&path);
+            std::fs::remove_file(&path).unwrap();
+            let genome = genome.unwrap();
+
+            assert_eq!(
+                ron::to_string(&genome.rna).unwrap(),
+                ron::to_string(&rna).unwrap()
+            );
+            assert_eq!(genome.components, registry.definitions(rna.keys()));
+        }
+    }
+}
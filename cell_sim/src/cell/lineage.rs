@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::{log, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::timing::SimClock;
+
+/// Identifies a cell for the whole run. Unlike [Entity], IDs are never reused and survive
+/// snapshots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CellId(pub u64);
+
+impl fmt::Display for CellId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Puts pairs of touching cells in order of their [CellId]s, the lower one first in each pair.
+/// Rapier does not report contacts in the same order every run, so systems acting on them sort
+/// them first to keep runs with the same seed identical. Pairs that are not both cells are
+/// dropped.
+pub(super) fn sort_contacts(
+    contacts: impl IntoIterator<Item = (Entity, Entity)>,
+    id: impl Fn(Entity) -> Option<CellId>,
+) -> Vec<(Entity, Entity)> {
+    let mut contacts: Vec<((CellId, CellId), (Entity, Entity))> = contacts
+        .into_iter()
+        .filter_map(|(a, b)| {
+            let (id_a, id_b) = (id(a)?, id(b)?);
+            Some(match id_a <= id_b {
+                true => ((id_a, id_b), (a, b)),
+                false => ((id_b, id_a), (b, a)),
+            })
+        })
+        .collect();
+    contacts.sort_by_key(|(ids, _)| *ids);
+    contacts.dedup_by_key(|(ids, _)| *ids);
+    contacts.into_iter().map(|(_, pair)| pair).collect()
+}
+
+/// Where a cell comes from. Times are [SimClock] times.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LineageRecord {
+    pub id: CellId,
+    /// The cell that divided to create this one. `None` for cells spawned at the start of a run.
+    pub parent: Option<CellId>,
+    /// The other parent when the cell was created by [super::conjugation::conjugate_cells].
+    pub mate: Option<CellId>,
+    /// Divisions since the first cell of the lineage. Cells spawned at the start are generation 0.
+    pub generation: u32,
+    pub born: f64,
+    pub died: Option<f64>,
+}
+
+/// Every cell that ever lived, so the phylogeny can be reconstructed after a run.
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct Lineage {
+    next_id: u64,
+    deaths: u64,
+    records: BTreeMap<CellId, LineageRecord>,
+}
+
+impl Lineage {
+    /// Records a new cell and returns its record, to be stored in [super::CellData::lineage].
+    pub fn birth(
+        &mut self,
+        parent: Option<&LineageRecord>,
+        mate: Option<&LineageRecord>,
+        born: f64,
+    ) -> LineageRecord {
+        let record = LineageRecord {
+            id: CellId(self.next_id),
+            parent: parent.map(|parent| parent.id),
+            mate: mate.map(|mate| mate.id),
+            generation: parent.map_or(0, |parent| parent.generation + 1),
+            born,
+            died: None,
+        };
+        self.next_id += 1;
+        self.records.insert(record.id, record.clone());
+
+        record
+    }
+
+    pub fn death(&mut self, id: CellId, died: f64) {
+        if let Some(record) = self.records.get_mut(&id) {
+            record.died = Some(died);
+            self.deaths += 1;
+        }
+    }
+
+    /// Cells born since the run started, including the ones spawned at the start.
+    pub fn births(&self) -> u64 {
+        self.next_id
+    }
+
+    /// Cells died since the run started.
+    pub fn deaths(&self) -> u64 {
+        self.deaths
+    }
+
+    pub fn get(&self, id: CellId) -> Option<&LineageRecord> {
+        self.records.get(&id)
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &LineageRecord> {
+        self.records.values()
+    }
+
+    /// The phylogeny in Newick format. Nodes are labelled with their [CellId] and branch lengths
+    /// are the time between the births of parent and child. Trees of cells spawned at the start
+    /// are joined under an unlabelled root.
+    pub fn to_newick(&self) -> String {
+        let mut children: BTreeMap<Option<CellId>, Vec<&LineageRecord>> = BTreeMap::new();
+        for record in self.records.values() {
+            children.entry(record.parent).or_default().push(record);
+        }
+
+        let length = |record: &LineageRecord| {
+            record
+                .parent
+                .and_then(|parent| self.records.get(&parent))
+                .map_or(0., |parent| record.born - parent.born)
+        };
+
+        // Written without recursion, lineages can be many thousands of generations deep.
+        enum Step<'a> {
+            Open(&'a LineageRecord),
+            Close(&'a LineageRecord),
+            Separator,
+        }
+        fn push_children<'a>(
+            steps: &mut Vec<Step<'a>>,
+            children: &BTreeMap<Option<CellId>, Vec<&'a LineageRecord>>,
+            parent: Option<CellId>,
+        ) {
+            let records = children
+                .get(&parent)
+                .map_or(&[][..], |records| &records[..]);
+            for (i, record) in records.iter().enumerate().rev() {
+                steps.push(Step::Open(record));
+                if i > 0 {
+                    steps.push(Step::Separator);
+                }
+            }
+        }
+        let mut newick = String::from("(");
+        let mut steps = Vec::new();
+        push_children(&mut steps, &children, None);
+
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Open(record) if children.contains_key(&Some(record.id)) => {
+                    newick.push('(');
+                    steps.push(Step::Close(record));
+                    push_children(&mut steps, &children, Some(record.id));
+                }
+                Step::Open(record) => {
+                    let _ = write!(newick, "{}:{}", record.id, length(record));
+                }
+                Step::Close(record) => {
+                    let _ = write!(newick, "){}:{}", record.id, length(record));
+                }
+                Step::Separator => newick.push(','),
+            }
+        }
+        newick.push_str(");");
+
+        newick
+    }
+
+    /// Every record as a line of `id,parent,mate,generation,born,died`. Unknown values are left
+    /// empty.
+    pub fn to_csv(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let mut csv = String::from("id,parent,mate,generation,born,died\n");
+        for record in self.records.values() {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                record.id,
+                optional(record.parent.map(|id| id.to_string())),
+                optional(record.mate.map(|id| id.to_string())),
+                record.generation,
+                record.born,
+                optional(record.died.map(|time| time.to_string())),
+            );
+        }
+
+        csv
+    }
+
+    /// Writes `lineage.nwk` and `lineage.csv` to `dir`.
+    pub fn export(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join("lineage.nwk"), self.to_newick())?;
+        fs::write(dir.join("lineage.csv"), self.to_csv())
+    }
+}
+
+/// Where the [Lineage] is exported to while running.
+#[derive(Resource)]
+pub struct LineageSettings {
+    /// Exported to with L. See [Lineage::export].
+    pub dir: PathBuf,
+    /// Exports every time this finishes.
+    pub autosave: Option<Timer>,
+}
+
+impl Default for LineageSettings {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("lineage"),
+            autosave: None,
+        }
+    }
+}
+
+pub fn export_lineage_system(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut settings: ResMut<LineageSettings>,
+    lineage: Res<Lineage>,
+    clock: Res<SimClock>,
+) {
+    let autosave = match &mut settings.autosave {
+        Some(timer) => timer.tick(time.delta()).just_finished(),
+        None => false,
+    };
+    if !autosave && !keys.just_pressed(KeyCode::L) {
+        return;
+    }
+
+    match lineage.export(&settings.dir) {
+        Ok(()) => log::info!(
+            "Exported lineage at {:.1} to {}",
+            clock.elapsed,
+            settings.dir.display()
+        ),
+        Err(e) => log::error!("Could not export lineage: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A root with two children, the first of which had a child with the second.
+    fn lineage() -> Lineage {
+        let mut lineage = Lineage::default();
+        let root = lineage.birth(None, None, 0.);
+        let first = lineage.birth(Some(&root), None, 1.5);
+        let second = lineage.birth(Some(&root), None, 2.);
+        lineage.birth(Some(&first), Some(&second), 4.);
+        lineage.death(second.id, 5.);
+        lineage
+    }
+
+    #[test]
+    fn newick_nests_children_with_branch_lengths() {
+        assert_eq!(lineage().to_newick(), "(((3:2.5)1:1.5,2:2)0:0);");
+    }
+
+    #[test]
+    fn csv_has_a_line_per_record() {
+        assert_eq!(
+            lineage().to_csv(),
+            "id,parent,mate,generation,born,died\n\
+             0,,,0,0,\n\
+             1,0,,1,1.5,\n\
+             2,0,,1,2,5\n\
+             3,1,2,2,4,\n"
+        );
+    }
+
+    #[test]
+    fn empty_lineage_is_an_empty_tree() {
+        assert_eq!(Lineage::default().to_newick(), "();");
+    }
+}
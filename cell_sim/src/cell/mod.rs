@@ -1,10 +1,52 @@
+mod behaviour;
 mod cell_base;
 mod cell_bundle;
 mod cell_components;
 mod cell_internals;
 mod component_instances;
+mod component_state;
+mod conjugation;
+mod crossover;
 mod ctl;
-mod weights;
+mod genome;
+mod lineage;
+mod mutation;
+mod predation;
+mod registry;
 mod rna;
+mod rng;
+mod snapshot;
+mod storage;
+mod weights;
 
+pub use behaviour::{
+    Behaviour, EfficiencyCurve, Reaction, Reagent, Substance, POLYSACCHARIDE_COMPLEXITY,
+};
+pub use cell_base::{
+    Cell, CellComponentType, CellData, Offspring, Surroundings, REGULATORY_SIGNALS,
+    SECRETED_SIGNALS, SENSE_CHANNELS, SIGNAL_PROTEINS,
+};
+pub use cell_bundle::{CellMesh, CellRenderAssets};
+pub use cell_components::CellComponent;
+pub use cell_internals::{CellInternals, Polysaccharide, SignalProtein};
+pub use component_instances::{core_components, ComponentBuilderProps, ComponentSpec};
+pub use component_state::{Replication, StateEffect, StateVariable};
+pub use conjugation::{conjugate_cells, ReproductionMode};
 pub use ctl::*;
+pub use genome::{archive_genome, load_genome, save_genome, Genome, SeedGenomes};
+pub use lineage::{export_lineage_system, CellId, Lineage, LineageRecord, LineageSettings};
+pub use mutation::MutationRates;
+pub use predation::{engulf_cells, ENGULF_SIZE_RATIO};
+pub use registry::{
+    load_components, save_components, ComponentId, ComponentInfo, ComponentKind, ComponentRegistry,
+    RegisterCellComponent, COMPONENTS_FILE,
+};
+pub use rna::RNA;
+pub use rng::{log_seed, SimRng};
+pub use snapshot::{
+    load_snapshot, load_snapshot_system, restore_pending_snapshot, restore_snapshot, save_snapshot,
+    save_snapshot_system, take_snapshot, CellSnapshot, ComponentSnapshot, PendingSnapshot,
+    SnapshotSettings, WorldSnapshot,
+};
+pub use storage::StorageError;
+pub use weights::{Sensitivity, Weight, WeightList};
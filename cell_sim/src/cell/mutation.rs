@@ -0,0 +1,327 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::cell_base::SIGNAL_PROTEINS;
+use super::component_instances::ComponentBuilderProps;
+use super::registry::{ComponentId, ComponentRegistry};
+use super::rna::RNA;
+use super::weights::{Weight, WeightList};
+
+/// Chances and magnitudes of the mutation operators applied to the [RNA] of every new cell.
+///
+/// Rates are probabilities in [0, 1] and are rolled once per gene (for gene level operators) or
+/// once per [Weight] (for [WeightList] operators).
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct MutationRates {
+    /// Chance of a gene being switched on or off.
+    pub toggle: f32,
+    /// Chance of [ComponentBuilderProps::size] and [ComponentBuilderProps::proteins] being
+    /// perturbed.
+    pub props: f32,
+    /// Maximum relative change of a perturbed prop.
+    pub props_magnitude: f32,
+    /// Chance of a [Weight] being jittered.
+    pub weight_jitter: f32,
+    /// Maximum absolute change of a jittered [Weight]'s values.
+    pub weight_jitter_magnitude: f32,
+    /// Chance of a [Weight] reacting to a different, random signal protein.
+    pub sensitivity_retarget: f32,
+    /// Chance of a new random [Weight] being inserted next to an existing one.
+    pub weight_insert: f32,
+    /// Chance of a [Weight] being deleted.
+    pub weight_delete: f32,
+}
+
+impl Default for MutationRates {
+    fn default() -> Self {
+        Self {
+            toggle: 0.01,
+            props: 0.1,
+            props_magnitude: 0.1,
+            weight_jitter: 0.01,
+            weight_jitter_magnitude: 10.,
+            sensitivity_retarget: 0.001,
+            weight_insert: 0.001,
+            weight_delete: 0.001,
+        }
+    }
+}
+
+fn roll(rng: &mut impl Rng, chance: f32) -> bool {
+    rng.gen::<f32>() < chance
+}
+
+/// Random value in [-magnitude, magnitude].
+fn noise(rng: &mut impl Rng, magnitude: f32) -> f32 {
+    (rng.gen::<f32>() * 2. - 1.) * magnitude
+}
+
+/// Returns a mutated copy of `rna`. The original is left untouched so that the parent keeps its
+/// own genome. Every component in `registry` gets a gene, so genomes from before a component was
+/// registered can still switch it on. Genes of components that are not registered are kept.
+pub fn mutate_rna(
+    rna: &RNA,
+    rates: &MutationRates,
+    registry: &ComponentRegistry,
+    rng: &mut impl Rng,
+) -> RNA {
+    let ids: BTreeSet<&ComponentId> = rna
+        .keys()
+        .chain(registry.iter().map(|info| &info.id))
+        .collect();
+    ids.into_iter()
+        .map(|id| {
+            let gene = rna.get(id).cloned().flatten();
+            let gene = match (gene, roll(rng, rates.toggle)) {
+                (Some(_), true) => None,
+                (None, true) => Some(ComponentBuilderProps::random(rng)),
+                (gene, false) => gene,
+            };
+
+            let gene = gene.map(|mut props| {
+                mutate_props(&mut props, rates, rng);
+                props
+            });
+            (id.clone(), gene)
+        })
+        .collect()
+}
+
+pub fn mutate_props(props: &mut ComponentBuilderProps, rates: &MutationRates, rng: &mut impl Rng) {
+    if roll(rng, rates.props) {
+        props.size = (props.size * (1. + noise(rng, rates.props_magnitude))).max(0.);
+        props.proteins = (props.proteins * (1. + noise(rng, rates.props_magnitude))).max(0.);
+    }
+    mutate_weightlist(&mut props.weightlist, rates, rng);
+}
+
+pub fn mutate_weightlist(weightlist: &mut WeightList, rates: &MutationRates, rng: &mut impl Rng) {
+    weightlist.update(|weights| {
+        let mut inserted = Vec::new();
+        for weight in weights.iter_mut() {
+            if roll(rng, rates.weight_jitter) {
+                let magnitude = rates.weight_jitter_magnitude;
+                weight.index = (weight.index + noise(rng, magnitude / 10.)).max(0.);
+                weight.range = (weight.range + noise(rng, magnitude / 10.)).max(0.);
+                weight.base += noise(rng, magnitude);
+                weight.sensitivity.weight += noise(rng, magnitude);
+            }
+            if roll(rng, rates.sensitivity_retarget) {
+                weight.sensitivity.index = rng.gen_range(0..SIGNAL_PROTEINS);
+            }
+            if roll(rng, rates.weight_insert) {
+                inserted.push(Weight {
+                    index: weight.index,
+                    ..Weight::random(rng)
+                });
+            }
+        }
+
+        // Never delete the last weight, a WeightList must not be empty.
+        let mut remaining = weights.len();
+        weights.retain(|_| {
+            if remaining > 1 && roll(rng, rates.weight_delete) {
+                remaining -= 1;
+                return false;
+            }
+            true
+        });
+
+        weights.extend(inserted);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn rng() -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(11)
+    }
+
+    fn no_mutations() -> MutationRates {
+        MutationRates {
+            toggle: 0.,
+            props: 0.,
+            props_magnitude: 0.5,
+            weight_jitter: 0.,
+            weight_jitter_magnitude: 10.,
+            sensitivity_retarget: 0.,
+            weight_insert: 0.,
+            weight_delete: 0.,
+        }
+    }
+
+    fn rna(registry: &ComponentRegistry, rng: &mut ChaCha8Rng) -> RNA {
+        registry
+            .iter()
+            .enumerate()
+            .map(|(i, info)| {
+                let gene = (i % 2 == 0).then(|| ComponentBuilderProps::random(&mut *rng));
+                (info.id.clone(), gene)
+            })
+            .collect()
+    }
+
+    fn weights(weightlist: &WeightList) -> Vec<(f32, f32, f32, usize, f32)> {
+        weightlist
+            .get()
+            .iter()
+            .map(|w| {
+                (
+                    w.index,
+                    w.range,
+                    w.base,
+                    w.sensitivity.index,
+                    w.sensitivity.weight,
+                )
+            })
+            .collect()
+    }
+
+    fn mutated_weights(rates: &MutationRates) -> (WeightList, WeightList) {
+        let mut rng = rng();
+        // Random lists are not sorted, go through new so they compare in order.
+        let original = WeightList::new(WeightList::random(&mut rng).get().clone());
+        let mut mutated = original.clone();
+        mutate_weightlist(&mut mutated, rates, &mut rng);
+        (original, mutated)
+    }
+
+    #[test]
+    fn nothing_changes_at_rate_zero() {
+        let registry = ComponentRegistry::default();
+        let mut rng = rng();
+        let rna = rna(&registry, &mut rng);
+        let mutated = mutate_rna(&rna, &no_mutations(), &registry, &mut rng);
+        assert_eq!(
+            ron::to_string(&mutated).unwrap(),
+            ron::to_string(&rna).unwrap()
+        );
+    }
+
+    #[test]
+    fn toggle_at_rate_one_flips_every_gene() {
+        let registry = ComponentRegistry::default();
+        let mut rng = rng();
+        let rna = rna(&registry, &mut rng);
+        let rates = MutationRates {
+            toggle: 1.,
+            ..no_mutations()
+        };
+        let mutated = mutate_rna(&rna, &rates, &registry, &mut rng);
+        for (id, gene) in &rna {
+            assert_eq!(mutated[id].is_some(), gene.is_none());
+        }
+    }
+
+    #[test]
+    fn genomes_gain_genes_for_newly_registered_components() {
+        let registry = ComponentRegistry::default();
+        let rates = MutationRates {
+            toggle: 1.,
+            ..no_mutations()
+        };
+        let mutated = mutate_rna(&RNA::new(), &rates, &registry, &mut rng());
+        assert_eq!(mutated.len(), registry.len());
+        assert!(mutated.values().all(Option::is_some));
+    }
+
+    #[test]
+    fn props_change_only_at_rate_one() {
+        let mut rng = rng();
+        let original = ComponentBuilderProps::random(&mut rng);
+
+        let mut props = original.clone();
+        mutate_props(&mut props, &no_mutations(), &mut rng);
+        assert_eq!(
+            (props.size, props.proteins),
+            (original.size, original.proteins)
+        );
+
+        let rates = MutationRates {
+            props: 1.,
+            ..no_mutations()
+        };
+        mutate_props(&mut props, &rates, &mut rng);
+        assert_ne!(props.size, original.size);
+        assert_ne!(props.proteins, original.proteins);
+    }
+
+    #[test]
+    fn weights_stay_the_same_at_rate_zero() {
+        let (original, mutated) = mutated_weights(&no_mutations());
+        assert_eq!(weights(&mutated), weights(&original));
+    }
+
+    #[test]
+    fn jitter_at_rate_one_changes_every_weight() {
+        let (original, mutated) = mutated_weights(&MutationRates {
+            weight_jitter: 1.,
+            ..no_mutations()
+        });
+        let original = weights(&original);
+        let mutated = weights(&mutated);
+        assert_eq!(mutated.len(), original.len());
+        // Jittered indices reorder the list, so look for weights that kept their values anywhere.
+        let unchanged = mutated
+            .iter()
+            .filter(|m| original.iter().any(|o| o.2 == m.2 && o.4 == m.4))
+            .count();
+        assert_eq!(unchanged, 0);
+    }
+
+    #[test]
+    fn retarget_at_rate_one_moves_sensitivities() {
+        let (original, mutated) = mutated_weights(&MutationRates {
+            sensitivity_retarget: 1.,
+            ..no_mutations()
+        });
+        let original = weights(&original);
+        let mutated = weights(&mutated);
+        assert_eq!(mutated.len(), original.len());
+        assert!(mutated.iter().all(|w| w.3 < SIGNAL_PROTEINS));
+        // Only the sensitivity index changes, and with this many weights some must move.
+        let moved = original.iter().zip(&mutated).filter(|(o, m)| o.3 != m.3);
+        assert!(moved.count() > 0);
+        assert!(original
+            .iter()
+            .zip(&mutated)
+            .all(|(o, m)| (o.0, o.1, o.2, o.4) == (m.0, m.1, m.2, m.4)));
+    }
+
+    #[test]
+    fn insert_at_rate_one_doubles_the_weights() {
+        let (original, mutated) = mutated_weights(&MutationRates {
+            weight_insert: 1.,
+            ..no_mutations()
+        });
+        assert_eq!(mutated.get().len(), original.get().len() * 2);
+    }
+
+    #[test]
+    fn delete_at_rate_one_keeps_the_last_weight() {
+        let (_, mut mutated) = mutated_weights(&MutationRates {
+            weight_delete: 1.,
+            ..no_mutations()
+        });
+        assert_eq!(mutated.get().len(), 1);
+
+        // Nor does deleting from a list that is down to a single weight.
+        let rates = MutationRates {
+            weight_insert: 1.,
+            weight_delete: 1.,
+            ..no_mutations()
+        };
+        for _ in 0..10 {
+            mutate_weightlist(&mut mutated, &rates, &mut rng());
+            assert!(!mutated.get().is_empty());
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashSet;
+
+use bevy::{log, prelude::*};
+use bevy_rapier2d::geometry::CollidingEntities;
+
+use super::cell_base::Cell;
+use super::lineage::{sort_contacts, Lineage};
+use crate::timing::SimClock;
+
+/// A cell can only engulf cells this many times smaller than itself.
+pub const ENGULF_SIZE_RATIO: f32 = 1.5;
+
+/// Lets cells with a phagocytosis component eat smaller cells they touch. See
+/// [super::CellData::engulf]. Goes through every contact, not only new ones, so a cell that starts
+/// phagocytosis or outgrows a cell it is already touching can still eat it.
+pub fn engulf_cells(
+    mut commands: Commands,
+    touching: Query<(Entity, &CollidingEntities), With<Cell>>,
+    mut cells: Query<&mut Cell>,
+    mut lineage: ResMut<Lineage>,
+    clock: Res<SimClock>,
+) {
+    let mut eaten = HashSet::new();
+    let touching = touching
+        .iter()
+        .flat_map(|(a, colliding)| colliding.iter().map(move |b| (a, b)));
+    let contacts = sort_contacts(touching, |entity| {
+        cells.get(entity).ok().map(|cell| cell.data.lineage.id)
+    });
+    for (a, b) in contacts {
+        if eaten.contains(&a) || eaten.contains(&b) {
+            continue;
+        }
+        let Ok([cell_a, cell_b]) = cells.get_many_mut([a, b]) else {
+            continue;
+        };
+        let (mut predator, mut prey, prey_entity) = match cell_a.size() >= cell_b.size() {
+            true => (cell_a, cell_b, b),
+            false => (cell_b, cell_a, a),
+        };
+        // Cells that are about to die are despawned by [super::ctl::remove_dead_cells].
+        if predator.data.engulf <= 0.
+            || predator.size() < prey.size() * ENGULF_SIZE_RATIO
+            || prey.data.base.atp <= 0.1
+        {
+            continue;
+        }
+
+        // Daughters the prey was holding are eaten along with it.
+        prey.data.reabsorb_offspring();
+        let fraction = predator.data.engulf;
+        predator.data.base.absorb(&prey.data.base, fraction);
+        commands.entity(prey_entity).despawn_recursive();
+        lineage.death(prey.data.lineage.id, clock.elapsed);
+        eaten.insert(prey_entity);
+        log::info!("Cell {:?} was engulfed", prey_entity);
+    }
+}
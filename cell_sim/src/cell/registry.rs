@@ -0,0 +1,225 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use bevy::{log, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::behaviour::{Behaviour, EfficiencyCurve};
+use super::cell_base::CellComponentType;
+use super::cell_components::CellComponent;
+use super::component_instances::{register_core_components, ComponentSpec};
+use super::component_state::{Replication, StateVariable};
+use super::storage::{self, FileKind, StorageError};
+
+/// On-disk format of component definitions, see [load_components].
+pub const COMPONENTS_FILE: FileKind = FileKind {
+    magic: b"CSCD",
+    version: 1,
+};
+
+/// Stable name of a kind of [super::CellComponent]. Genomes and snapshots refer to components by
+/// this, so it must never change once released. Components from other crates should prefix it
+/// with the crate name, for instance `my_crate:chloroplast`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ComponentId(pub Cow<'static, str>);
+
+impl ComponentId {
+    pub const fn new(id: &'static str) -> Self {
+        Self(Cow::Borrowed(id))
+    }
+}
+
+impl fmt::Display for ComponentId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Where a component sits in the cell, see [super::Cell].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComponentKind {
+    Internal,
+    Membrane,
+}
+
+/// A kind of component and what it does. Plain data, so components can be defined in config
+/// files, see [load_components].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComponentInfo {
+    pub id: ComponentId,
+    /// Human readable name.
+    pub name: Cow<'static, str>,
+    pub kind: ComponentKind,
+    /// How well the component works given the size and proteins in its gene.
+    #[serde(default)]
+    pub efficiency: EfficiencyCurve,
+    /// Space the component takes up in the cell, or the size in its gene if not set.
+    #[serde(default)]
+    pub size: Option<f32>,
+    pub behaviour: Behaviour,
+    /// Values each component keeps between steps.
+    #[serde(default)]
+    pub state: Vec<StateVariable>,
+    #[serde(default)]
+    pub replication: Option<Replication>,
+}
+
+impl ComponentInfo {
+    /// Amount of values the component reads from its [super::WeightList] each step.
+    pub fn args(&self) -> usize {
+        self.behaviour.args()
+    }
+
+    /// Checks that the definition can be run, so definitions loaded from files are rejected
+    /// instead of misbehaving or panicking once cells are built from them.
+    pub fn validate(&self) -> Result<(), StorageError> {
+        let invalid =
+            |reason: String| StorageError::Invalid(format!("component {}: {}", self.id, reason));
+        if self.kind == ComponentKind::Internal && self.behaviour.needs_membrane() {
+            return Err(invalid("only works in the membrane".into()));
+        }
+        self.behaviour.validate().map_err(invalid)?;
+        if let Some(replication) = &self.replication {
+            if replication.state >= self.state.len() {
+                return Err(invalid(format!(
+                    "replication reads state {}, but there are {}",
+                    replication.state,
+                    self.state.len()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks every definition with [ComponentInfo::validate].
+pub(super) fn validate_components(infos: &[ComponentInfo]) -> Result<(), StorageError> {
+    infos.iter().try_for_each(ComponentInfo::validate)
+}
+
+/// Every kind of component cells can be built from. Genes are keyed by [ComponentId], so
+/// registering new components does not break existing genomes.
+///
+/// Comes with the core components. Other crates can add their own with
+/// [RegisterCellComponent::register_cell_component].
+#[derive(Resource, Clone)]
+pub struct ComponentRegistry {
+    /// In registration order, which is the order genes are read from a [super::WeightList] in
+    /// [super::rna::build_rna].
+    components: Vec<Arc<ComponentInfo>>,
+    indices: HashMap<ComponentId, usize>,
+}
+
+impl Default for ComponentRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        register_core_components(&mut registry);
+        registry
+    }
+}
+
+impl ComponentRegistry {
+    /// A registry without even the core components.
+    pub fn empty() -> Self {
+        Self {
+            components: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    /// Panics if a component with the same [ComponentId] is already registered.
+    pub fn register(&mut self, info: ComponentInfo) {
+        assert!(
+            !self.indices.contains_key(&info.id),
+            "component {} is already registered",
+            info.id
+        );
+        self.indices.insert(info.id.clone(), self.components.len());
+        self.components.push(Arc::new(info));
+    }
+
+    /// Registers the components that are not registered yet, such as the ones saved with a
+    /// genome or snapshot. Components that are already registered are kept as they are.
+    pub fn merge(&mut self, infos: impl IntoIterator<Item = ComponentInfo>) {
+        for info in infos {
+            match self.get(&info.id) {
+                Some(registered) if *registered != info => log::warn!(
+                    "component {} is already registered with a different definition, keeping it",
+                    info.id
+                ),
+                Some(_) => {}
+                None => self.register(info),
+            }
+        }
+    }
+
+    pub fn get(&self, id: &ComponentId) -> Option<&ComponentInfo> {
+        self.indices.get(id).map(|&i| &*self.components[i])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ComponentInfo> {
+        self.components.iter().map(|info| &**info)
+    }
+
+    /// Definitions of the registered components among `ids`, to be saved alongside them.
+    pub fn definitions<'a>(
+        &self,
+        ids: impl IntoIterator<Item = &'a ComponentId>,
+    ) -> Vec<ComponentInfo> {
+        ids.into_iter()
+            .filter_map(|id| self.get(id).cloned())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Builds the component described by `spec`, or returns `None` if its [ComponentId] is not
+    /// registered.
+    pub fn build(&self, spec: ComponentSpec) -> Option<CellComponentType> {
+        let info = self.components[*self.indices.get(&spec.id)?].clone();
+        let kind = info.kind;
+        let component = CellComponent::new(info, spec);
+        Some(match kind {
+            ComponentKind::Internal => CellComponentType::Internal(component),
+            ComponentKind::Membrane => CellComponentType::Membrane(component),
+        })
+    }
+}
+
+/// Registers components on an [App]. Usable before or after [crate::CellSimPlugin] is added.
+pub trait RegisterCellComponent {
+    fn register_cell_component(&mut self, info: ComponentInfo) -> &mut Self;
+}
+
+impl RegisterCellComponent for App {
+    fn register_cell_component(&mut self, info: ComponentInfo) -> &mut Self {
+        self.init_resource::<ComponentRegistry>();
+        self.world
+            .resource_mut::<ComponentRegistry>()
+            .register(info);
+        self
+    }
+}
+
+/// Loads component definitions, to be registered with [ComponentRegistry::merge]. RON, or compact
+/// binary if the path ends in `.bin`.
+pub fn load_components(path: &Path) -> Result<Vec<ComponentInfo>, StorageError> {
+    let infos: Vec<ComponentInfo> = storage::load(path, &COMPONENTS_FILE)?;
+    validate_components(&infos)?;
+    Ok(infos)
+}
+
+pub fn save_components(path: &Path, infos: &[ComponentInfo]) -> Result<(), StorageError> {
+    storage::save(path, &COMPONENTS_FILE, &infos)
+}
@@ -1,11 +1,17 @@
+use std::collections::BTreeMap;
+
 use crate::cell::weights::{Sensitivity, Weight};
 
+use super::cell_base::SIGNAL_PROTEINS;
 use super::cell_internals::SignalProtein;
-use super::component_instances::{register_component_builders, ComponentBuilderProps};
+use super::component_instances::ComponentBuilderProps;
+use super::registry::{ComponentId, ComponentRegistry};
 use super::weights::WeightList;
 
+/// The genes of a cell, one per kind of component. A gene is `None` when the component is
+/// switched off.
 #[allow(clippy::upper_case_acronyms)]
-pub type RNA = Vec<Option<ComponentBuilderProps>>;
+pub type RNA = BTreeMap<ComponentId, Option<ComponentBuilderProps>>;
 
 fn args_and_weights(
     weightlist: &WeightList,
@@ -34,28 +40,33 @@ fn args_and_weights(
     [args_weightlist, weights_weightlist]
 }
 
+/// Maps a value in [-1, 1] onto one of the [SIGNAL_PROTEINS].
+fn signal_index(value: f32) -> usize {
+    (((value + 1.) / 2. * SIGNAL_PROTEINS as f32) as usize).min(SIGNAL_PROTEINS - 1)
+}
+
 pub fn build_rna(
     weightlist: &WeightList,
     cell_size: f32,
     signal_proteins: &[SignalProtein],
+    registry: &ComponentRegistry,
 ) -> RNA {
     let mut rna = RNA::new();
-    let components = register_component_builders();
     let weights_amount = weightlist.get_val_at(0, cell_size, signal_proteins) as usize;
 
     let [args_weightlist, weights_weightlist] =
         args_and_weights(weightlist, cell_size, signal_proteins);
 
     const ARGS_CHUNK_SIZE: usize = 3;
-    let args_size = components.len() * ARGS_CHUNK_SIZE;
+    let args_size = registry.len() * ARGS_CHUNK_SIZE;
     let binding = args_weightlist.get_split_vals(cell_size, signal_proteins, args_size);
     let args = binding.chunks(ARGS_CHUNK_SIZE);
 
     const WEIGHT_SIZE: usize = 5;
     let weightlist_arg_size = WEIGHT_SIZE * weights_amount;
-    let total_weightlist_arg_size = components.len() * weightlist_arg_size;
+    let total_weightlist_arg_size = registry.len() * weightlist_arg_size;
 
-    // components.len() chunks of length `weightlist_arg_size`
+    // registry.len() chunks of length `weightlist_arg_size`
     let binding =
         weights_weightlist.get_split_vals(cell_size, signal_proteins, total_weightlist_arg_size);
     let sensitivities = binding.chunks(if weightlist_arg_size > 0 {
@@ -64,7 +75,7 @@ pub fn build_rna(
         1
     });
 
-    for (arg_chunk, sensitivity_chunk) in args.zip(sensitivities) {
+    for ((arg_chunk, sensitivity_chunk), info) in args.zip(sensitivities).zip(registry.iter()) {
         let [activation, size, proteins] = *arg_chunk else {
             todo!()
         };
@@ -76,7 +87,7 @@ pub fn build_rna(
                     base: *c.get(1).unwrap_or(&0.),
                     range: *c.get(2).unwrap_or(&0.),
                     sensitivity: Sensitivity {
-                        index: *c.get(3).unwrap_or(&0.) as usize,
+                        index: signal_index(*c.get(3).unwrap_or(&0.)),
                         weight: *c.get(4).unwrap_or(&0.),
                     },
                 })
@@ -92,7 +103,7 @@ pub fn build_rna(
             false => None,
         };
 
-        rna.push(result);
+        rna.insert(info.id.clone(), result);
     }
 
     rna
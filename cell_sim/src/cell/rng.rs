@@ -0,0 +1,43 @@
+use bevy::{log, prelude::*};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// Random number generator owned by a single [super::cell_base::Cell]. Each cell has its own
+/// stream so cells can be updated in parallel and still be reproducible.
+pub type CellRng = ChaCha8Rng;
+
+/// Random number generator for everything that isn't owned by a cell, such as where the initial
+/// cells are placed. Every other stream is derived from this one, so a run is fully determined by
+/// the seed it was started with, as long as physics is too. That takes the `enhanced-determinism`
+/// feature: with the default `fast-physics`, Rapier's results may differ in the last bits between
+/// runs and cells drift apart over time.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct SimRng {
+    pub seed: u64,
+    pub rng: ChaCha8Rng,
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Derives a new independent stream for a cell.
+    pub fn cell_rng(&mut self) -> CellRng {
+        derive_rng(&mut self.rng)
+    }
+}
+
+/// Logs the seed so the run can be reproduced with `--seed`.
+pub fn log_seed(sim_rng: Res<SimRng>) {
+    log::info!("seed: {}", sim_rng.seed);
+}
+
+/// Derives a new independent stream from `rng`, for instance for a daughter cell.
+pub fn derive_rng(rng: &mut impl Rng) -> CellRng {
+    CellRng::seed_from_u64(rng.gen())
+}
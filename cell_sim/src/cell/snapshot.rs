@@ -0,0 +1,288 @@
+use std::path::{Path, PathBuf};
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::{log, prelude::*};
+use bevy_rapier2d::dynamics::Velocity;
+use serde::{Deserialize, Serialize};
+
+use super::cell_base::{Cell, CellComponentType, CellData};
+use super::cell_bundle::CellBundle;
+use super::cell_components::CellComponent;
+use super::component_instances::ComponentSpec;
+use super::conjugation::ReproductionMode;
+use super::lineage::Lineage;
+use super::mutation::MutationRates;
+use super::registry::{validate_components, ComponentInfo, ComponentRegistry};
+use super::rng::SimRng;
+use super::storage::{self, FileKind, StorageError};
+use crate::nutrients::{NutrientField, NutrientSettings};
+use crate::scene::{spawn_walls, Wall, WorldBounds};
+use crate::signals::{SignalField, SignalSettings};
+use crate::stats::StatsRecorder;
+use crate::timing::{SimClock, SimTiming};
+
+/// On-disk world snapshot format. Bump the version whenever the layout of any saved type changes.
+pub const SNAPSHOT_FILE: FileKind = FileKind {
+    magic: b"CSWS",
+    version: 11,
+};
+
+/// Everything needed to resume a simulation.
+#[derive(Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub bounds: WorldBounds,
+    pub timing: SimTiming,
+    pub mutation_rates: MutationRates,
+    pub reproduction_mode: ReproductionMode,
+    pub sim_rng: SimRng,
+    pub nutrient_settings: NutrientSettings,
+    pub nutrients: NutrientField,
+    pub signal_settings: SignalSettings,
+    pub signals: SignalField,
+    pub clock: SimClock,
+    pub lineage: Lineage,
+    /// Every registered component, so cells can be restored in a run that does not have them
+    /// registered.
+    pub components: Vec<ComponentInfo>,
+    pub cells: Vec<CellSnapshot>,
+}
+
+/// A [Cell] and its physical state. The components are rebuilt when the cell is restored, see
+/// [WorldSnapshot::components].
+#[derive(Serialize, Deserialize)]
+pub struct CellSnapshot {
+    pub internal_components: Vec<ComponentSnapshot>,
+    pub membrane_components: Vec<ComponentSnapshot>,
+    pub data: CellData,
+    pub transform: Transform,
+    pub linear_velocity: Vec2,
+    pub angular_velocity: f32,
+}
+
+/// A [CellComponent] as the [ComponentSpec] it was built from and its [CellComponent::state].
+#[derive(Serialize, Deserialize)]
+pub struct ComponentSnapshot {
+    pub spec: ComponentSpec,
+    pub state: Vec<f32>,
+}
+
+impl ComponentSnapshot {
+    pub fn new(component: &CellComponent) -> Self {
+        Self {
+            spec: component.spec.clone(),
+            state: component.state.clone(),
+        }
+    }
+
+    /// Returns `None` if the component is not registered. The state is only restored if the
+    /// component still declares as much state as was saved.
+    pub fn restore(self, registry: &ComponentRegistry) -> Option<CellComponentType> {
+        let mut component = registry.build(self.spec)?;
+        let (CellComponentType::Internal(built) | CellComponentType::Membrane(built)) =
+            &mut component;
+        if built.state.len() == self.state.len() {
+            built.state = self.state;
+        }
+        Some(component)
+    }
+}
+
+impl CellSnapshot {
+    pub fn new(cell: &Cell, transform: &Transform, velocity: &Velocity) -> Self {
+        Self {
+            internal_components: component_snapshots(&cell.internal_components),
+            membrane_components: component_snapshots(&cell.membrane_components),
+            data: cell.data.clone(),
+            transform: *transform,
+            linear_velocity: velocity.linvel,
+            angular_velocity: velocity.angvel,
+        }
+    }
+
+    pub fn restore(self, registry: &ComponentRegistry) -> CellBundle {
+        let mut cell = Cell {
+            internal_components: vec![],
+            membrane_components: vec![],
+            data: self.data,
+        };
+        for saved in self
+            .internal_components
+            .into_iter()
+            .chain(self.membrane_components)
+        {
+            if let Some(component) = saved.restore(registry) {
+                cell.inject_component(component);
+            }
+        }
+
+        let mut bundle = CellBundle::new(cell, self.transform.translation);
+        bundle.transform_bundle = TransformBundle::from(self.transform);
+        bundle.velocity = Velocity {
+            linvel: self.linear_velocity,
+            angvel: self.angular_velocity,
+        };
+        bundle
+    }
+}
+
+fn component_snapshots(components: &[CellComponent]) -> Vec<ComponentSnapshot> {
+    components.iter().map(ComponentSnapshot::new).collect()
+}
+
+pub fn save_snapshot(path: &Path, snapshot: &WorldSnapshot) -> Result<(), StorageError> {
+    storage::save(path, &SNAPSHOT_FILE, snapshot)
+}
+
+pub fn load_snapshot(path: &Path) -> Result<WorldSnapshot, StorageError> {
+    let snapshot: WorldSnapshot = storage::load(path, &SNAPSHOT_FILE)?;
+    validate_components(&snapshot.components)?;
+    Ok(snapshot)
+}
+
+/// Where snapshots are saved to and loaded from while running.
+#[derive(Resource)]
+pub struct SnapshotSettings {
+    /// Saved to with F5 and loaded from with F9. RON, or compact binary if it ends in `.bin`.
+    pub path: PathBuf,
+    /// Saves a snapshot every time this finishes.
+    pub autosave: Option<Timer>,
+}
+
+impl Default for SnapshotSettings {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("snapshot.ron"),
+            autosave: None,
+        }
+    }
+}
+
+/// Snapshot to restore before the simulation starts, instead of spawning new cells.
+#[derive(Resource)]
+pub struct PendingSnapshot(pub WorldSnapshot);
+
+/// Captures the current world, the way [restore_snapshot] puts it back.
+pub fn take_snapshot(world: &mut World) -> WorldSnapshot {
+    let cells = world
+        .query::<(&Cell, &Transform, &Velocity)>()
+        .iter(world)
+        .map(|(cell, transform, velocity)| CellSnapshot::new(cell, transform, velocity))
+        .collect();
+
+    WorldSnapshot {
+        bounds: *world.resource::<WorldBounds>(),
+        timing: *world.resource::<SimTiming>(),
+        mutation_rates: world.resource::<MutationRates>().clone(),
+        reproduction_mode: *world.resource::<ReproductionMode>(),
+        sim_rng: world.resource::<SimRng>().clone(),
+        nutrient_settings: world.resource::<NutrientSettings>().clone(),
+        nutrients: world.resource::<NutrientField>().clone(),
+        signal_settings: world.resource::<SignalSettings>().clone(),
+        signals: world.resource::<SignalField>().clone(),
+        clock: *world.resource::<SimClock>(),
+        lineage: world.resource::<Lineage>().clone(),
+        components: world
+            .resource::<ComponentRegistry>()
+            .iter()
+            .cloned()
+            .collect(),
+        cells,
+    }
+}
+
+/// Saves a snapshot to [SnapshotSettings::path] when F5 is pressed or it is time to autosave.
+pub fn save_snapshot_system(world: &mut World) {
+    let delta = world.resource::<Time>().delta();
+    let requested = world.resource::<Input<KeyCode>>().just_pressed(KeyCode::F5);
+    let mut settings = world.resource_mut::<SnapshotSettings>();
+    let autosave = match &mut settings.autosave {
+        Some(timer) => timer.tick(delta).just_finished(),
+        None => false,
+    };
+    if !autosave && !requested {
+        return;
+    }
+
+    let path = settings.path.clone();
+    let snapshot = take_snapshot(world);
+    match save_snapshot(&path, &snapshot) {
+        Ok(()) => log::info!("Saved {} cells to {}", snapshot.cells.len(), path.display()),
+        Err(e) => log::error!("Could not save snapshot: {}", e),
+    }
+}
+
+/// Replaces the world with the snapshot at [SnapshotSettings::path] when F9 is pressed.
+pub fn load_snapshot_system(world: &mut World) {
+    if !world.resource::<Input<KeyCode>>().just_pressed(KeyCode::F9) {
+        return;
+    }
+
+    let path = world.resource::<SnapshotSettings>().path.clone();
+    match load_snapshot(&path) {
+        Ok(snapshot) => {
+            restore_snapshot(world, snapshot);
+            // The walls were built for the old bounds.
+            let walls: Vec<Entity> = world
+                .query_filtered::<Entity, With<Wall>>()
+                .iter(world)
+                .collect();
+            for wall in walls {
+                world.despawn(wall);
+            }
+            world.run_system_once(spawn_walls);
+            log::info!("Loaded snapshot {}", path.display());
+        }
+        Err(e) => log::error!("Could not load snapshot {}: {}", path.display(), e),
+    }
+}
+
+/// Restores a [PendingSnapshot] if there is one. Runs before anything else is spawned.
+pub fn restore_pending_snapshot(world: &mut World) {
+    if let Some(PendingSnapshot(snapshot)) = world.remove_resource::<PendingSnapshot>() {
+        restore_snapshot(world, snapshot);
+    }
+}
+
+/// Replaces all cells and the world configuration with the snapshot.
+pub fn restore_snapshot(world: &mut World, snapshot: WorldSnapshot) {
+    let cells: Vec<Entity> = world
+        .query_filtered::<Entity, With<Cell>>()
+        .iter(world)
+        .collect();
+    for cell in cells {
+        world.entity_mut(cell).despawn_recursive();
+    }
+
+    world.insert_resource(snapshot.bounds);
+    world.insert_resource(snapshot.timing);
+    world.insert_resource(snapshot.mutation_rates);
+    if let Some(mode) = world.get_resource::<ReproductionMode>() {
+        if *mode != snapshot.reproduction_mode {
+            log::warn!(
+                "Snapshot reproduces {:?}, replacing {:?}",
+                snapshot.reproduction_mode,
+                mode
+            );
+        }
+    }
+    world.insert_resource(snapshot.reproduction_mode);
+    world.insert_resource(snapshot.sim_rng);
+    world.insert_resource(snapshot.nutrient_settings);
+    world.insert_resource(snapshot.nutrients);
+    world.insert_resource(snapshot.signal_settings);
+    world.insert_resource(snapshot.signals);
+    if let Some(mut recorder) = world.get_resource_mut::<StatsRecorder>() {
+        recorder.resync(&snapshot.lineage, &snapshot.clock);
+    }
+    world.insert_resource(snapshot.clock);
+    world.insert_resource(snapshot.lineage);
+    let mut registry = world.resource_mut::<ComponentRegistry>();
+    registry.merge(snapshot.components);
+    let registry = world.resource::<ComponentRegistry>();
+    let cells: Vec<CellBundle> = snapshot
+        .cells
+        .into_iter()
+        .map(|cell| cell.restore(registry))
+        .collect();
+    world.spawn_batch(cells);
+}
@@ -0,0 +1,240 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// How data is stored on disk. Picked from the file extension, see [StorageFormat::from_path].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageFormat {
+    /// Human readable, for inspecting and hand editing.
+    Ron,
+    /// Compact, for archiving.
+    Binary,
+}
+
+impl StorageFormat {
+    /// `.bin` files are [StorageFormat::Binary], everything else is [StorageFormat::Ron].
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("bin") => Self::Binary,
+            _ => Self::Ron,
+        }
+    }
+}
+
+/// Identifies what kind of data a file holds, and which version of its layout.
+pub struct FileKind {
+    /// Magic bytes at the start of a [StorageFormat::Binary] file.
+    pub magic: &'static [u8; 4],
+    pub version: u32,
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(io::Error),
+    Ron(ron::Error),
+    Binary(bincode::Error),
+    /// The file does not hold the expected kind of data.
+    BadMagic,
+    /// The file was written with an incompatible version of the layout.
+    Version {
+        found: u32,
+        expected: u32,
+    },
+    /// The file decoded fine but what it describes makes no sense, for instance a component
+    /// reading signals that do not exist.
+    Invalid(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::Ron(e) => write!(f, "ron error: {}", e),
+            Self::Binary(e) => write!(f, "binary error: {}", e),
+            Self::BadMagic => write!(f, "unexpected kind of file"),
+            Self::Version { found, expected } => write!(
+                f,
+                "version {} is not supported, expected {}",
+                found, expected
+            ),
+            Self::Invalid(reason) => write!(f, "invalid data: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<io::Error> for StorageError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ron::Error> for StorageError {
+    fn from(e: ron::Error) -> Self {
+        Self::Ron(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for StorageError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        Self::Ron(e.code)
+    }
+}
+
+impl From<bincode::Error> for StorageError {
+    fn from(e: bincode::Error) -> Self {
+        Self::Binary(e)
+    }
+}
+
+/// Layout of every file. The version comes first so it can be checked before the data is
+/// decoded.
+#[derive(Serialize, Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    data: T,
+}
+
+pub fn write<T: Serialize>(
+    writer: impl Write,
+    kind: &FileKind,
+    format: StorageFormat,
+    data: &T,
+) -> Result<(), StorageError> {
+    let versioned = Versioned {
+        version: kind.version,
+        data,
+    };
+
+    match format {
+        StorageFormat::Ron => {
+            ron::ser::to_writer_pretty(writer, &versioned, ron::ser::PrettyConfig::default())?
+        }
+        StorageFormat::Binary => {
+            let mut writer = writer;
+            writer.write_all(kind.magic)?;
+            bincode::serialize_into(writer, &versioned)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn read<T: DeserializeOwned>(
+    reader: impl Read,
+    kind: &FileKind,
+    format: StorageFormat,
+) -> Result<T, StorageError> {
+    let versioned: Versioned<T> = match format {
+        StorageFormat::Ron => ron::de::from_reader(reader)?,
+        StorageFormat::Binary => {
+            let mut reader = reader;
+            let mut magic = [0; 4];
+            reader.read_exact(&mut magic)?;
+            if &magic != kind.magic {
+                return Err(StorageError::BadMagic);
+            }
+            let version: u32 = bincode::deserialize_from(&mut reader)?;
+            check_version(version, kind)?;
+            Versioned {
+                version,
+                data: bincode::deserialize_from(reader)?,
+            }
+        }
+    };
+    check_version(versioned.version, kind)?;
+
+    Ok(versioned.data)
+}
+
+fn check_version(found: u32, kind: &FileKind) -> Result<(), StorageError> {
+    match found == kind.version {
+        true => Ok(()),
+        false => Err(StorageError::Version {
+            found,
+            expected: kind.version,
+        }),
+    }
+}
+
+pub fn save<T: Serialize>(path: &Path, kind: &FileKind, data: &T) -> Result<(), StorageError> {
+    let writer = BufWriter::new(File::create(path)?);
+    write(writer, kind, StorageFormat::from_path(path), data)
+}
+
+pub fn load<T: DeserializeOwned>(path: &Path, kind: &FileKind) -> Result<T, StorageError> {
+    let reader = BufReader::new(File::open(path)?);
+    read(reader, kind, StorageFormat::from_path(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIND: FileKind = FileKind {
+        magic: b"TEST",
+        version: 2,
+    };
+    const OLD_KIND: FileKind = FileKind {
+        magic: b"TEST",
+        version: 1,
+    };
+
+    fn written(kind: &FileKind, format: StorageFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write(&mut bytes, kind, format, &(7u32, String::from("data"))).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trips_in_both_formats() {
+        for format in [StorageFormat::Ron, StorageFormat::Binary] {
+            let bytes = written(&KIND, format);
+            let data: (u32, String) = read(bytes.as_slice(), &KIND, format).unwrap();
+            assert_eq!(data, (7, String::from("data")));
+        }
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        for format in [StorageFormat::Ron, StorageFormat::Binary] {
+            let bytes = written(&OLD_KIND, format);
+            let result: Result<(u32, String), _> = read(bytes.as_slice(), &KIND, format);
+            assert!(matches!(
+                result,
+                Err(StorageError::Version {
+                    found: 1,
+                    expected: 2
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_other_kinds_of_file() {
+        let other = FileKind {
+            magic: b"ELSE",
+            version: 2,
+        };
+        let bytes = written(&other, StorageFormat::Binary);
+        let result: Result<(u32, String), _> = read(bytes.as_slice(), &KIND, StorageFormat::Binary);
+        assert!(matches!(result, Err(StorageError::BadMagic)));
+    }
+
+    #[test]
+    fn picks_format_from_extension() {
+        assert_eq!(
+            StorageFormat::from_path(Path::new("genome.bin")),
+            StorageFormat::Binary
+        );
+        assert_eq!(
+            StorageFormat::from_path(Path::new("genome.ron")),
+            StorageFormat::Ron
+        );
+    }
+}
@@ -1,14 +1,17 @@
 use bevy::log;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
+use super::cell_base::SIGNAL_PROTEINS;
 use super::cell_internals::SignalProtein;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Sensitivity {
     pub index: usize,
     pub weight: f32,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Weight {
     pub index: f32,
     pub range: f32,
@@ -16,11 +19,47 @@ pub struct Weight {
     pub sensitivity: Sensitivity,
 }
 
-#[derive(Clone)]
+impl Weight {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self {
+            index: rng.gen::<f32>() * 100.,
+            range: rng.gen::<f32>() * 100.,
+            base: rng.gen::<f32>() * 1000. - 500.,
+            sensitivity: Sensitivity {
+                index: (rng.gen::<f32>() * SIGNAL_PROTEINS as f32) as usize,
+                weight: rng.gen::<f32>() * 1000. - 500.,
+            },
+        }
+    }
+}
+
+/// Serialized as a plain list of [Weight]s, which is validated when deserializing.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "Vec<Weight>", into = "Vec<Weight>")]
 pub struct WeightList {
     weights: Vec<Weight>,
 }
 
+impl TryFrom<Vec<Weight>> for WeightList {
+    type Error = &'static str;
+
+    fn try_from(weights: Vec<Weight>) -> Result<Self, Self::Error> {
+        if weights.is_empty() {
+            return Err("WeightList must have at least one weight");
+        }
+        if weights.iter().any(|w| w.index.is_nan()) {
+            return Err("Weight index must not be NaN");
+        }
+        Ok(Self::new(weights))
+    }
+}
+
+impl From<WeightList> for Vec<Weight> {
+    fn from(weightlist: WeightList) -> Self {
+        weightlist.weights
+    }
+}
+
 // Vector stuff
 impl WeightList {
     pub fn new(mut weights: Vec<Weight>) -> Self {
@@ -31,13 +70,11 @@ impl WeightList {
         Self { weights }
     }
     pub fn append(&mut self, weight: Weight) {
-        if let Ok(index) = self
+        let index = self
             .weights
             .binary_search_by(|a| a.index.partial_cmp(&weight.index).unwrap())
-        {
-            self.weights.insert(index, weight)
-        }
-        self.weights.push(weight);
+            .unwrap_or_else(|index| index);
+        self.weights.insert(index, weight);
     }
     pub fn extend(&mut self, mut weights: Vec<Weight>) {
         weights.sort_by(|a, b| a.index.partial_cmp(&b.index).unwrap());
@@ -49,6 +86,16 @@ impl WeightList {
     pub fn get(&self) -> &Vec<Weight> {
         &self.weights
     }
+    /// Mutably access the raw weights. The weights are sorted again afterwards, so
+    /// [Weight::index] may be changed freely, but the list must not be left empty.
+    pub fn update(&mut self, f: impl FnOnce(&mut Vec<Weight>)) {
+        f(&mut self.weights);
+        if self.weights.is_empty() {
+            panic!("WeightList must have at least one weight");
+        }
+        self.weights
+            .sort_by(|a, b| a.index.partial_cmp(&b.index).unwrap());
+    }
     pub fn get_val_at(
         &self,
         index: usize,
@@ -124,20 +171,12 @@ impl WeightList {
     }
 }
 
-impl Default for WeightList {
-    fn default() -> Self {
-        let weight_size = (rand::random::<f32>() * 1000.) as usize + 100;
+impl WeightList {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        let weight_size = (rng.gen::<f32>() * 1000.) as usize + 100;
         let mut weights = Vec::with_capacity(weight_size);
         for _ in 0..weight_size {
-            weights.push(Weight {
-                index: rand::random::<f32>() * 100.,
-                range: rand::random::<f32>() * 100.,
-                base: rand::random::<f32>() * 1000. - 500.,
-                sensitivity: Sensitivity {
-                    index: (rand::random::<f32>() * 100.) as usize,
-                    weight: rand::random::<f32>() * 1000. - 500.,
-                },
-            });
+            weights.push(Weight::random(rng));
         }
 
         Self { weights }
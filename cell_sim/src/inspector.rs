@@ -0,0 +1,198 @@
+use std::fmt::Write as _;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::cell::Cell;
+
+/// The cell shown in the inspector panel, picked by clicking on it.
+#[derive(Resource, Default)]
+pub struct SelectedCell(pub Option<Entity>);
+
+/// Marks the inspector panel.
+#[derive(Component)]
+pub struct InspectorPanel;
+
+/// Marks the text inside the [InspectorPanel].
+#[derive(Component)]
+pub struct InspectorText;
+
+pub fn spawn_inspector(mut commands: Commands) {
+    commands
+        .spawn((
+            InspectorPanel,
+            // Lets clicks on the panel be told apart from clicks on the world.
+            Interaction::default(),
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.),
+                    right: Val::Px(10.),
+                    width: Val::Px(320.),
+                    padding: UiRect::all(Val::Px(8.)),
+                    display: Display::None,
+                    ..Default::default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.7).into(),
+                ..Default::default()
+            },
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                InspectorText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 14.,
+                        color: Color::WHITE,
+                        ..Default::default()
+                    },
+                ),
+            ));
+        });
+}
+
+/// Selects the cell under the cursor on left click, or clears the selection when clicking on
+/// nothing.
+pub fn select_cell(
+    buttons: Res<Input<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    panels: Query<&Interaction, With<InspectorPanel>>,
+    cells: Query<(Entity, &Cell, &Transform)>,
+    mut selected: ResMut<SelectedCell>,
+) {
+    if !buttons.just_pressed(MouseButton::Left)
+        || panels
+            .iter()
+            .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+    let Some(cursor) = windows.get_single().ok().and_then(Window::cursor_position) else {
+        return;
+    };
+    let Some(position) = cameras
+        .iter()
+        .find_map(|(camera, transform)| camera.viewport_to_world_2d(transform, cursor))
+    else {
+        return;
+    };
+
+    selected.0 = cells
+        .iter()
+        .filter_map(|(entity, cell, transform)| {
+            let distance = transform.translation.truncate().distance(position);
+            (distance <= cell.radius()).then_some((entity, distance))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity);
+}
+
+pub fn update_inspector(
+    mut selected: ResMut<SelectedCell>,
+    cells: Query<(&Cell, &Transform)>,
+    mut panels: Query<&mut Style, With<InspectorPanel>>,
+    mut texts: Query<&mut Text, With<InspectorText>>,
+    mut gizmos: Gizmos,
+) {
+    // The selected cell may have died since.
+    let cell = selected.0.and_then(|entity| cells.get(entity).ok());
+    if cell.is_none() {
+        selected.0 = None;
+    }
+
+    let display = match cell {
+        Some(_) => Display::Flex,
+        None => Display::None,
+    };
+    for mut style in panels.iter_mut() {
+        // Only written when it changes, to not lay out the UI again every frame.
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    let Some((cell, transform)) = cell else {
+        return;
+    };
+
+    gizmos.circle_2d(
+        transform.translation.truncate(),
+        cell.radius() + 2.,
+        Color::WHITE,
+    );
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = describe_cell(cell);
+    }
+}
+
+/// Everything the inspector shows about a cell.
+pub fn describe_cell(cell: &Cell) -> String {
+    let data = &cell.data;
+    let base = &data.base;
+    let mut text = String::new();
+
+    let _ = writeln!(text, "Cell {}", data.lineage.id);
+    let _ = writeln!(
+        text,
+        "generation {}, born at {:.1}",
+        data.lineage.generation, data.lineage.born
+    );
+    let _ = writeln!(text, "speed {:.2}  size {:.2}", data.speed, data.size);
+    let _ = writeln!(
+        text,
+        "velocity ({:.1}, {:.1})",
+        data.velocity.x, data.velocity.y
+    );
+
+    let _ = writeln!(text, "\nInternals");
+    let _ = writeln!(text, "  atp {:.2}", base.atp);
+    let _ = writeln!(text, "  glucose {:.2}", base.glucose);
+    let _ = writeln!(
+        text,
+        "  polysaccharides {:.2}",
+        base.polysaccharides.iter().map(|p| p.amount).sum::<f32>()
+    );
+    let _ = writeln!(text, "  proteins {:.2}", base.proteins);
+    let _ = writeln!(text, "  amino acids {:.2}", base.amino_acids);
+    let _ = writeln!(text, "  nucleotides {:.2}", base.nucleotides);
+    let signals: Vec<String> = base
+        .signal_proteins
+        .iter()
+        .enumerate()
+        .filter(|(_, signal)| signal.amount() != 0.)
+        .map(|(i, signal)| format!("{}: {:.2}", i, signal.amount()))
+        .collect();
+    let _ = writeln!(text, "  signals [{}]", signals.join(", "));
+
+    for (title, components) in [
+        ("Internal components", &cell.internal_components),
+        ("Membrane components", &cell.membrane_components),
+    ] {
+        let _ = writeln!(text, "\n{}", title);
+        for component in components {
+            let _ = write!(text, "  {} size {:.2}", component.spec.id, component.size);
+            for (name, value) in component.state() {
+                let _ = write!(text, " {} {:.2}", name, value);
+            }
+            let _ = writeln!(text);
+        }
+    }
+
+    let _ = writeln!(text, "\nRNA");
+    for (id, gene) in &data.rna {
+        let _ = match gene {
+            Some(props) => writeln!(
+                text,
+                "  {} size {:.2} proteins {:.2}, {} weights",
+                id,
+                props.size,
+                props.proteins,
+                props.weightlist.get().len()
+            ),
+            None => writeln!(text, "  {} off", id),
+        };
+    }
+
+    text
+}
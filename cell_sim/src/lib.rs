@@ -0,0 +1,14 @@
+//! Simulation of evolving cells, packaged as Bevy plugins. Add [CellSimPlugin] to run the
+//! simulation and [CellSimRenderPlugin] to draw it.
+pub mod camera;
+pub mod cell;
+pub mod inspector;
+pub mod nutrients;
+mod plugin;
+pub mod scene;
+pub mod signals;
+pub mod stats;
+pub mod timing;
+
+pub use cell::{Cell, CellComponent, CellData, RNA};
+pub use plugin::{CellSimPlugin, CellSimRenderPlugin, CellSimSet, CellSimSettings};
@@ -1,29 +1,148 @@
 use bevy::prelude::*;
 use bevy_fps_counter::FpsCounterPlugin;
-use bevy_rapier2d::prelude::*;
-mod cell;
-mod scene;
+mod args;
+use std::time::Duration;
+
+use bevy::app::ScheduleRunnerPlugin;
 use bevy::core::TaskPoolThreadAssignmentPolicy;
 use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
-use cell::*;
-use scene::spawn_camera;
+use bevy::render::settings::WgpuSettings;
+use bevy::render::RenderPlugin;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use cell_sim::cell::*;
+use cell_sim::scene::spawn_camera;
+use cell_sim::stats::StatsSettings;
+use cell_sim::{CellSimPlugin, CellSimRenderPlugin, CellSimSettings};
+
+#[cfg(all(feature = "fast-physics", feature = "enhanced-determinism"))]
+compile_error!(
+    "fast-physics and enhanced-determinism cannot be combined, build with \
+     `--no-default-features --features enhanced-determinism`"
+);
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(TaskPoolPlugin {
-            task_pool_options: TaskPoolOptions {
-                io: TaskPoolThreadAssignmentPolicy {
-                    min_threads: 1,
-                    max_threads: 2,
-                    percent: 0.2,
-                },
+    let args = args::Args::parse();
+    let mut registry = ComponentRegistry::default();
+    for path in &args.components {
+        registry.merge(load_components(path).unwrap_or_else(|e| {
+            eprintln!("could not load components {}: {}", path.display(), e);
+            std::process::exit(1);
+        }));
+    }
+    let seed_genomes = args
+        .genomes
+        .iter()
+        .map(|path| {
+            let genome = load_genome(path).unwrap_or_else(|e| {
+                eprintln!("could not load genome {}: {}", path.display(), e);
+                std::process::exit(1);
+            });
+            registry.merge(genome.components);
+            genome.rna
+        })
+        .collect();
+    let pending_snapshot = args.load.as_ref().map(|path| {
+        let mut snapshot = load_snapshot(path).unwrap_or_else(|e| {
+            eprintln!("could not load snapshot {}: {}", path.display(), e);
+            std::process::exit(1);
+        });
+        // --reproduction wins over the mode the snapshot was saved with.
+        if let Some(mode) = args.reproduction {
+            if snapshot.reproduction_mode != mode {
+                eprintln!(
+                    "snapshot {} reproduces {:?}, using {:?} instead",
+                    path.display(),
+                    snapshot.reproduction_mode,
+                    mode
+                );
+                snapshot.reproduction_mode = mode;
+            }
+        }
+        snapshot
+    });
+    let mut snapshot_settings = SnapshotSettings {
+        autosave: args
+            .autosave
+            .map(|seconds| Timer::from_seconds(seconds, TimerMode::Repeating)),
+        ..Default::default()
+    };
+    if let Some(path) = args.snapshot {
+        snapshot_settings.path = path;
+    }
+    let mut lineage_settings = LineageSettings {
+        autosave: args
+            .autosave
+            .map(|seconds| Timer::from_seconds(seconds, TimerMode::Repeating)),
+        ..Default::default()
+    };
+    if let Some(dir) = args.lineage {
+        lineage_settings.dir = dir;
+    }
+
+    let mut stats_settings = StatsSettings {
+        path: args.stats,
+        ..Default::default()
+    };
+    if let Some(interval) = args.stats_interval {
+        stats_settings.interval = interval;
+    }
+
+    let default_plugins = DefaultPlugins.set(TaskPoolPlugin {
+        task_pool_options: TaskPoolOptions {
+            io: TaskPoolThreadAssignmentPolicy {
+                min_threads: 1,
+                max_threads: 2,
+                percent: 0.2,
+            },
+            ..Default::default()
+        },
+    });
+
+    let mut app = App::new();
+    match args.headless {
+        // No window and no GPU, the app is driven by the schedule runner instead of winit.
+        true => app.add_plugins((
+            default_plugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                })
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings {
+                        backends: None,
+                        ..Default::default()
+                    }
+                    .into(),
+                })
+                .disable::<WinitPlugin>(),
+            ScheduleRunnerPlugin::run_loop(Duration::ZERO),
+        )),
+        false => app
+            .add_plugins((default_plugins, CellSimRenderPlugin, FpsCounterPlugin))
+            .add_systems(Startup, spawn_camera),
+    };
+    if let Some(mode) = args.reproduction {
+        app.insert_resource(mode);
+    }
+    if let Some(snapshot) = pending_snapshot {
+        app.insert_resource(PendingSnapshot(snapshot));
+    }
+
+    app.insert_resource(registry)
+        .insert_resource(SeedGenomes(seed_genomes))
+        .insert_resource(snapshot_settings)
+        .insert_resource(lineage_settings)
+        .insert_resource(stats_settings)
+        .add_plugins(CellSimPlugin {
+            settings: CellSimSettings {
+                world_size: args.world_bounds.unwrap_or_default(),
+                seed: args.seed,
                 ..Default::default()
             },
-        }))
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(1000.0))
-        .add_plugins(FpsCounterPlugin)
-        .add_systems(Startup, cell::spawn_cells)
-        .add_systems(Startup, spawn_camera)
-        .add_systems(Update, update_all_cells)
+        })
+        .add_systems(Update, (archive_genome, export_lineage_system))
+        .add_systems(Update, (save_snapshot_system, load_snapshot_system))
         .run();
 }
@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cell::Cell;
+use crate::scene::WorldBounds;
+use crate::timing::SimTiming;
+
+/// Where glucose enters the world. See [NutrientSettings::sources].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NutrientSource {
+    /// Relative to [WorldBounds], (0, 0) is the bottom left corner and (1, 1) the top right.
+    pub position: Vec2,
+    pub radius: f32,
+    /// Glucose added to every tile within [NutrientSource::radius] per unit of simulated time.
+    pub rate: f32,
+}
+
+/// How the [NutrientField] behaves.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct NutrientSettings {
+    /// Width and height of a tile of the field.
+    pub tile_size: f32,
+    /// Glucose every tile starts with.
+    pub initial: f32,
+    /// Most glucose a tile can hold. Sources stop adding to full tiles.
+    pub capacity: f32,
+    /// Fraction of the difference to each neighbour exchanged per unit of simulated time.
+    pub diffusion: f32,
+    pub sources: Vec<NutrientSource>,
+}
+
+impl Default for NutrientSettings {
+    fn default() -> Self {
+        Self {
+            tile_size: 20.,
+            initial: 5.,
+            capacity: 20.,
+            diffusion: 0.1,
+            sources: vec![
+                NutrientSource {
+                    position: Vec2::new(0.25, 0.5),
+                    radius: 150.,
+                    rate: 0.5,
+                },
+                NutrientSource {
+                    position: Vec2::new(0.75, 0.5),
+                    radius: 150.,
+                    rate: 0.5,
+                },
+            ],
+        }
+    }
+}
+
+/// Glucose in the environment, stored on a grid of square tiles covering [WorldBounds]. Cells
+/// take glucose from the tile they are on with membrane components, see
+/// [crate::cell::Surroundings].
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct NutrientField {
+    pub tile_size: f32,
+    pub columns: usize,
+    pub rows: usize,
+    /// Row major, starting at the bottom left.
+    pub glucose: Vec<f32>,
+}
+
+impl NutrientField {
+    pub fn new(bounds: &WorldBounds, settings: &NutrientSettings) -> Self {
+        let columns = (bounds.width / settings.tile_size).ceil().max(1.) as usize;
+        let rows = (bounds.height / settings.tile_size).ceil().max(1.) as usize;
+
+        Self {
+            tile_size: settings.tile_size,
+            columns,
+            rows,
+            glucose: vec![settings.initial; columns * rows],
+        }
+    }
+
+    /// Index of the tile at `position`. Positions outside the field are clamped to its edge.
+    pub fn tile_at(&self, position: Vec2) -> usize {
+        let column = ((position.x / self.tile_size).max(0.) as usize).min(self.columns - 1);
+        let row = ((position.y / self.tile_size).max(0.) as usize).min(self.rows - 1);
+
+        row * self.columns + column
+    }
+
+    /// Center of the tile at `index`.
+    pub fn tile_center(&self, index: usize) -> Vec2 {
+        let column = index % self.columns;
+        let row = index / self.columns;
+
+        Vec2::new(column as f32 + 0.5, row as f32 + 0.5) * self.tile_size
+    }
+
+    /// Direction glucose increases in around the tile at `index`, per unit of distance.
+    pub fn gradient(&self, index: usize) -> Vec2 {
+        let column = index % self.columns;
+        let row = index / self.columns;
+        let at = |column: usize, row: usize| self.glucose[row * self.columns + column];
+
+        let (left, right) = (column.saturating_sub(1), (column + 1).min(self.columns - 1));
+        let (down, up) = (row.saturating_sub(1), (row + 1).min(self.rows - 1));
+        let dx = match right - left {
+            0 => 0.,
+            span => (at(right, row) - at(left, row)) / (span as f32 * self.tile_size),
+        };
+        let dy = match up - down {
+            0 => 0.,
+            span => (at(column, up) - at(column, down)) / (span as f32 * self.tile_size),
+        };
+
+        Vec2::new(dx, dy)
+    }
+
+    pub fn total(&self) -> f32 {
+        self.glucose.iter().sum()
+    }
+
+    /// Spreads glucose to neighbouring tiles, see [diffuse_grid].
+    pub fn diffuse(&mut self, diffusion: f32, dt: f32) {
+        diffuse_grid(&mut self.glucose, self.columns, self.rows, diffusion, dt);
+    }
+
+    /// Adds glucose around every source, up to [NutrientSettings::capacity].
+    pub fn replenish(&mut self, bounds: &WorldBounds, settings: &NutrientSettings, dt: f32) {
+        let size = Vec2::new(bounds.width, bounds.height);
+        for source in &settings.sources {
+            let center = source.position * size;
+            for i in 0..self.glucose.len() {
+                if self.tile_center(i).distance(center) <= source.radius {
+                    self.glucose[i] = (self.glucose[i] + source.rate * dt)
+                        .min(settings.capacity)
+                        .max(self.glucose[i]);
+                }
+            }
+        }
+    }
+}
+
+/// Spreads the values of a row major grid to neighbouring tiles. The amount leaving a tile is
+/// capped so the grid stays stable for large `dt`.
+pub fn diffuse_grid(values: &mut [f32], columns: usize, rows: usize, diffusion: f32, dt: f32) {
+    let rate = (diffusion * dt).min(0.25);
+    let old = values.to_vec();
+
+    for row in 0..rows {
+        for column in 0..columns {
+            let i = row * columns + column;
+            let mut flow = 0.;
+            if column > 0 {
+                flow += old[i - 1] - old[i];
+            }
+            if column + 1 < columns {
+                flow += old[i + 1] - old[i];
+            }
+            if row > 0 {
+                flow += old[i - columns] - old[i];
+            }
+            if row + 1 < rows {
+                flow += old[i + columns] - old[i];
+            }
+            values[i] = old[i] + flow * rate;
+        }
+    }
+}
+
+/// Creates the [NutrientField], unless one was restored from a snapshot.
+pub fn init_nutrient_field(
+    mut commands: Commands,
+    bounds: Res<WorldBounds>,
+    settings: Res<NutrientSettings>,
+    field: Option<Res<NutrientField>>,
+) {
+    if field.is_none() {
+        commands.insert_resource(NutrientField::new(&bounds, &settings));
+    }
+}
+
+/// Tells every cell how much glucose it can take this tick. Cells sharing a tile split it evenly.
+pub fn share_nutrients(field: Res<NutrientField>, mut cells: Query<(&mut Cell, &Transform)>) {
+    let mut occupants = vec![0u32; field.glucose.len()];
+    for (_, transform) in cells.iter() {
+        occupants[field.tile_at(transform.translation.truncate())] += 1;
+    }
+    for (mut cell, transform) in cells.iter_mut() {
+        let tile = field.tile_at(transform.translation.truncate());
+        let surroundings = &mut cell.data.surroundings;
+        surroundings.glucose = field.glucose[tile] / occupants[tile] as f32;
+        surroundings.glucose_taken = 0.;
+        surroundings.concentration = field.glucose[tile];
+        surroundings.gradient = field.gradient(tile);
+        surroundings.crowding = (occupants[tile] - 1) as f32;
+    }
+}
+
+/// Removes the glucose cells took this tick from the field.
+pub fn collect_nutrients(mut field: ResMut<NutrientField>, cells: Query<(&Cell, &Transform)>) {
+    for (cell, transform) in cells.iter() {
+        let tile = field.tile_at(transform.translation.truncate());
+        field.glucose[tile] = (field.glucose[tile] - cell.data.surroundings.glucose_taken).max(0.);
+    }
+}
+
+pub fn update_nutrient_field(
+    mut field: ResMut<NutrientField>,
+    bounds: Res<WorldBounds>,
+    settings: Res<NutrientSettings>,
+    timing: Res<SimTiming>,
+) {
+    let dt = timing.sim_time_per_tick;
+    field.replenish(&bounds, &settings, dt);
+    field.diffuse(settings.diffusion, dt);
+}
@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::camera::{
+    follow_camera_target, pan_and_zoom_camera, toggle_camera_follow, CameraFollow,
+};
+use crate::cell::*;
+use crate::inspector::{select_cell, spawn_inspector, update_inspector, SelectedCell};
+use crate::nutrients::{
+    collect_nutrients, init_nutrient_field, share_nutrients, update_nutrient_field,
+    NutrientSettings,
+};
+use crate::scene::{spawn_walls, WorldBounds};
+use crate::signals::{
+    collect_signals, init_signal_field, share_signals, update_signal_field, SignalSettings,
+};
+use crate::stats::{record_stats, StatsRecorder, StatsSettings};
+use crate::timing::{advance_sim_clock, apply_sim_timing, SimClock, SimTiming};
+
+/// Settings for [CellSimPlugin].
+#[derive(Resource, Clone, Debug)]
+pub struct CellSimSettings {
+    /// Cells spawned at startup, unless a [PendingSnapshot] is restored instead.
+    pub spawn_count: usize,
+    pub world_size: WorldBounds,
+    /// Scale of the physics simulation, see [RapierPhysicsPlugin::pixels_per_meter].
+    pub pixels_per_meter: f32,
+    /// Seed for [SimRng]. A random one is picked if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for CellSimSettings {
+    fn default() -> Self {
+        Self {
+            spawn_count: 500,
+            world_size: WorldBounds::default(),
+            pixels_per_meter: 1000.,
+            seed: None,
+        }
+    }
+}
+
+/// The stages of a [CellSimPlugin] step, run in this order in [FixedUpdate] before physics.
+///
+/// Each stage can be turned off or run conditionally with
+/// `app.configure_sets(FixedUpdate, CellSimSet::Reproduction.run_if(...))`, and systems can be
+/// added between stages by ordering them relative to these sets.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CellSimSet {
+    /// Advances the [SimClock] and tells cells about their surroundings and physics state.
+    Sense,
+    /// Runs the components of each cell.
+    Metabolism,
+    /// Spawns offspring, asexually or by conjugation.
+    Reproduction,
+    /// Updates the nutrient and signal fields from what cells took and released. Runs before
+    /// [CellSimSet::Death] so cells dying this tick still settle what they took.
+    Environment,
+    /// Removes cells that were eaten or ran out of ATP.
+    Death,
+    /// Hands the size and movement of cells back to physics.
+    PhysicsSync,
+    /// Records statistics of the step.
+    Record,
+    /// Keeps cell meshes in line with the cells. Runs in [Update], added by
+    /// [CellSimRenderPlugin].
+    Render,
+}
+
+/// The cell simulation, including its physics. Does not render anything, add
+/// [CellSimRenderPlugin] for that.
+///
+/// Resources such as [MutationRates], [ReproductionMode], [SimTiming], [SeedGenomes] and
+/// [ComponentRegistry] are initialised to their defaults unless they were inserted before this
+/// plugin is added.
+#[derive(Default)]
+pub struct CellSimPlugin {
+    pub settings: CellSimSettings,
+}
+
+impl Plugin for CellSimPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = &self.settings;
+
+        app.add_plugins(
+            RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(settings.pixels_per_meter)
+                .in_fixed_schedule(),
+        )
+        .insert_resource(settings.clone())
+        .insert_resource(settings.world_size)
+        .insert_resource(SimRng::new(settings.seed.unwrap_or_else(rand::random)))
+        .init_resource::<SimTiming>()
+        .init_resource::<SimClock>()
+        .init_resource::<Lineage>()
+        .init_resource::<LineageSettings>()
+        .init_resource::<MutationRates>()
+        .init_resource::<ReproductionMode>()
+        .init_resource::<SeedGenomes>()
+        .init_resource::<ComponentRegistry>()
+        .init_resource::<NutrientSettings>()
+        .init_resource::<SignalSettings>()
+        .init_resource::<SnapshotSettings>()
+        .init_resource::<StatsSettings>()
+        .init_resource::<StatsRecorder>()
+        .add_systems(PreStartup, restore_pending_snapshot)
+        .add_systems(Startup, log_seed)
+        .add_systems(Startup, spawn_cells.run_if(no_cells))
+        .add_systems(Startup, spawn_walls)
+        .add_systems(Startup, (init_nutrient_field, init_signal_field).chain())
+        .configure_sets(
+            FixedUpdate,
+            (
+                CellSimSet::Sense,
+                CellSimSet::Metabolism,
+                CellSimSet::Reproduction,
+                CellSimSet::Environment,
+                CellSimSet::Death,
+                CellSimSet::PhysicsSync,
+                CellSimSet::Record,
+            )
+                .chain()
+                .before(PhysicsSet::SyncBackend),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                (
+                    advance_sim_clock,
+                    share_nutrients,
+                    share_signals,
+                    read_cell_physics,
+                )
+                    .chain()
+                    .in_set(CellSimSet::Sense),
+                metabolize_cells.in_set(CellSimSet::Metabolism),
+                (reproduce_cells, conjugate_cells)
+                    .chain()
+                    .in_set(CellSimSet::Reproduction),
+                (engulf_cells, remove_dead_cells)
+                    .chain()
+                    .in_set(CellSimSet::Death),
+                write_cell_physics.in_set(CellSimSet::PhysicsSync),
+                (
+                    collect_nutrients,
+                    update_nutrient_field,
+                    collect_signals,
+                    update_signal_field,
+                )
+                    .chain()
+                    .in_set(CellSimSet::Environment),
+                record_stats.in_set(CellSimSet::Record),
+            ),
+        )
+        .add_systems(
+            PreUpdate,
+            apply_sim_timing.run_if(resource_changed::<SimTiming>()),
+        );
+    }
+}
+
+/// Draws the cells of a [CellSimPlugin], lets them be inspected by clicking on them and lets the
+/// camera be moved around. Does not add a camera, see [crate::scene::spawn_camera].
+pub struct CellSimRenderPlugin;
+
+impl Plugin for CellSimRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CellRenderAssets>()
+            .init_resource::<SelectedCell>()
+            .add_systems(Startup, spawn_inspector)
+            .add_systems(
+                Update,
+                (attach_cell_meshes, update_cell_meshes).in_set(CellSimSet::Render),
+            )
+            .init_resource::<CameraFollow>()
+            .add_systems(Update, (select_cell, update_inspector).chain())
+            .add_systems(
+                Update,
+                (
+                    pan_and_zoom_camera,
+                    toggle_camera_follow,
+                    follow_camera_target,
+                )
+                    .chain()
+                    .after(select_cell),
+            );
+    }
+}
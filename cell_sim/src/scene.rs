@@ -1,31 +1,61 @@
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
 use bevy_rapier2d::geometry::Collider;
+use serde::{Deserialize, Serialize};
 
-pub fn spawn_camera(mut commands: Commands, window_query: Query<&Window, With<PrimaryWindow>>) {
-    let window = window_query.single();
+use crate::camera::CameraController;
 
-    commands.spawn(Camera2dBundle {
-        transform: Transform::from_xyz(window.width() / 2., window.height() / 2., 0.),
-        ..Default::default()
-    });
+/// Size of the area cells live in. The world spans from the origin to (width, height) and is
+/// enclosed by walls, see [spawn_walls].
+#[derive(Resource, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct WorldBounds {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for WorldBounds {
+    /// Matches the size of the default window.
+    fn default() -> Self {
+        Self {
+            width: 1280.,
+            height: 720.,
+        }
+    }
+}
+
+/// Spawns a camera centred on the world. It can be moved around, see
+/// [crate::camera::CameraController].
+pub fn spawn_camera(mut commands: Commands, bounds: Res<WorldBounds>) {
+    commands.spawn((
+        Camera2dBundle {
+            transform: Transform::from_xyz(bounds.width / 2., bounds.height / 2., 0.),
+            ..Default::default()
+        },
+        CameraController::default(),
+    ));
+}
+
+/// Marks the walls enclosing the world, so they can be rebuilt when [WorldBounds] changes.
+#[derive(Component)]
+pub struct Wall;
+
+pub fn spawn_walls(mut commands: Commands, bounds: Res<WorldBounds>) {
     commands
-        .spawn(Collider::cuboid(window.width(), 10.))
+        .spawn((Wall, Collider::cuboid(bounds.width, 10.)))
         .insert(TransformBundle::from(Transform::from_xyz(0., 0., 0.)));
     commands
-        .spawn(Collider::cuboid(window.width(), 10.))
+        .spawn((Wall, Collider::cuboid(bounds.width, 10.)))
         .insert(TransformBundle::from(Transform::from_xyz(
             0.,
-            window.height(),
+            bounds.height,
             0.,
         )));
     commands
-        .spawn(Collider::cuboid(10., window.height()))
+        .spawn((Wall, Collider::cuboid(10., bounds.height)))
         .insert(TransformBundle::from(Transform::from_xyz(0., 0., 0.)));
     commands
-        .spawn(Collider::cuboid(10., window.height()))
+        .spawn((Wall, Collider::cuboid(10., bounds.height)))
         .insert(TransformBundle::from(Transform::from_xyz(
-            window.width(),
+            bounds.width,
             0.,
             0.,
         )));
@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cell::{Cell, SECRETED_SIGNALS};
+use crate::nutrients::{diffuse_grid, NutrientField};
+use crate::timing::SimTiming;
+
+/// How the [SignalField] behaves.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct SignalSettings {
+    /// Fraction of the difference to each neighbour exchanged per unit of simulated time.
+    pub diffusion: f32,
+    /// Fraction of every signal broken down per unit of simulated time.
+    pub decay: f32,
+}
+
+impl Default for SignalSettings {
+    fn default() -> Self {
+        Self {
+            diffusion: 0.2,
+            decay: 0.05,
+        }
+    }
+}
+
+/// Signal molecules secreted by cells, see [crate::cell::Behaviour::Secretion]. Uses the same
+/// tiles as the [NutrientField], so cells find their tile with [NutrientField::tile_at].
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct SignalField {
+    /// One grid per secreted signal, laid out like [NutrientField::glucose].
+    pub signals: Vec<Vec<f32>>,
+}
+
+impl SignalField {
+    pub fn new(nutrients: &NutrientField) -> Self {
+        Self {
+            signals: vec![vec![0.; nutrients.glucose.len()]; SECRETED_SIGNALS],
+        }
+    }
+
+    /// Amount of every signal on the tile at `index`.
+    pub fn at(&self, index: usize) -> [f32; SECRETED_SIGNALS] {
+        std::array::from_fn(|channel| self.signals[channel][index])
+    }
+
+    pub fn update(&mut self, nutrients: &NutrientField, settings: &SignalSettings, dt: f32) {
+        let keep = (1. - settings.decay * dt).max(0.);
+        for grid in self.signals.iter_mut() {
+            diffuse_grid(
+                grid,
+                nutrients.columns,
+                nutrients.rows,
+                settings.diffusion,
+                dt,
+            );
+            grid.iter_mut().for_each(|amount| *amount *= keep);
+        }
+    }
+}
+
+/// Creates the [SignalField], unless one was restored from a snapshot. Must run after
+/// [crate::nutrients::init_nutrient_field].
+pub fn init_signal_field(
+    mut commands: Commands,
+    nutrients: Res<NutrientField>,
+    field: Option<Res<SignalField>>,
+) {
+    if field.is_none() {
+        commands.insert_resource(SignalField::new(&nutrients));
+    }
+}
+
+/// Tells every cell how much of each secreted signal it can take this tick. Cells sharing a tile
+/// split it evenly. Must run after [crate::nutrients::share_nutrients], which counts them.
+pub fn share_signals(
+    nutrients: Res<NutrientField>,
+    field: Res<SignalField>,
+    mut cells: Query<(&mut Cell, &Transform)>,
+) {
+    for (mut cell, transform) in cells.iter_mut() {
+        let tile = nutrients.tile_at(transform.translation.truncate());
+        let surroundings = &mut cell.data.surroundings;
+        let occupants = surroundings.crowding + 1.;
+        surroundings.signals = field.at(tile).map(|amount| amount / occupants);
+        surroundings.signals_taken = [0.; SECRETED_SIGNALS];
+    }
+}
+
+/// Applies the signals cells took and released this tick to the field.
+pub fn collect_signals(
+    nutrients: Res<NutrientField>,
+    mut field: ResMut<SignalField>,
+    cells: Query<(&Cell, &Transform)>,
+) {
+    for (cell, transform) in cells.iter() {
+        let tile = nutrients.tile_at(transform.translation.truncate());
+        let taken = &cell.data.surroundings.signals_taken;
+        for (grid, taken) in field.signals.iter_mut().zip(taken) {
+            grid[tile] = (grid[tile] - taken).max(0.);
+        }
+    }
+}
+
+pub fn update_signal_field(
+    mut field: ResMut<SignalField>,
+    nutrients: Res<NutrientField>,
+    settings: Res<SignalSettings>,
+    timing: Res<SimTiming>,
+) {
+    field.update(&nutrients, &settings, timing.sim_time_per_tick);
+}
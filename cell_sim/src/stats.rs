@@ -0,0 +1,334 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use bevy::{log, prelude::*};
+use serde::Serialize;
+
+use crate::cell::{Cell, ComponentId, ComponentRegistry, Lineage};
+use crate::timing::SimClock;
+
+/// How a [StatsSettings::path] is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatsFormat {
+    /// One row per sample, with a column per component registered when the file is opened.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+impl StatsFormat {
+    /// `.jsonl` and `.json` files are [StatsFormat::JsonLines], everything else is
+    /// [StatsFormat::Csv].
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("jsonl" | "json") => Self::JsonLines,
+            _ => Self::Csv,
+        }
+    }
+}
+
+/// Where and how often population statistics are recorded.
+#[derive(Resource, Clone, Debug)]
+pub struct StatsSettings {
+    /// Nothing is recorded if not set. The file is overwritten when the first sample is taken.
+    pub path: Option<PathBuf>,
+    /// Simulated time between samples.
+    pub interval: f64,
+}
+
+impl Default for StatsSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            interval: 10.,
+        }
+    }
+}
+
+/// Mean and variance of a value over all cells.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct Summary {
+    pub mean: f32,
+    pub variance: f32,
+}
+
+impl Summary {
+    pub fn new(values: impl Iterator<Item = f32> + Clone) -> Self {
+        let count = values.clone().count();
+        if count == 0 {
+            return Self::default();
+        }
+        let mean = values.clone().sum::<f32>() / count as f32;
+        let variance = values.map(|v| (v - mean) * (v - mean)).sum::<f32>() / count as f32;
+
+        Self { mean, variance }
+    }
+}
+
+/// Statistics of the population at one point in time.
+#[derive(Clone, Debug, Serialize)]
+pub struct StatsSample {
+    /// [SimClock] time the sample was taken at.
+    pub time: f64,
+    pub population: usize,
+    /// Cells born since the previous sample.
+    pub births: u64,
+    /// Cells died since the previous sample.
+    pub deaths: u64,
+    pub atp: Summary,
+    pub glucose: Summary,
+    pub size: Summary,
+    pub speed: Summary,
+    /// Fraction of cells with at least one component of each registered kind.
+    pub components: BTreeMap<ComponentId, f32>,
+}
+
+impl StatsSample {
+    pub fn new(
+        cells: &[&Cell],
+        registry: &ComponentRegistry,
+        time: f64,
+        births: u64,
+        deaths: u64,
+    ) -> Self {
+        let population = cells.len();
+        let mut components: BTreeMap<ComponentId, f32> =
+            registry.iter().map(|info| (info.id.clone(), 0.)).collect();
+        for cell in cells {
+            let ids: BTreeSet<&ComponentId> = cell
+                .internal_components
+                .iter()
+                .chain(&cell.membrane_components)
+                .map(|component| &component.spec.id)
+                .collect();
+            for id in ids {
+                if let Some(count) = components.get_mut(id) {
+                    *count += 1.;
+                }
+            }
+        }
+        if population > 0 {
+            components
+                .values_mut()
+                .for_each(|count| *count /= population as f32);
+        }
+
+        Self {
+            time,
+            population,
+            births,
+            deaths,
+            atp: Summary::new(cells.iter().map(|cell| cell.data.base.atp)),
+            glucose: Summary::new(cells.iter().map(|cell| cell.data.base.glucose)),
+            size: Summary::new(cells.iter().map(|cell| cell.data.size)),
+            speed: Summary::new(cells.iter().map(|cell| cell.data.velocity.length())),
+            components,
+        }
+    }
+
+    fn csv_header(columns: &[ComponentId]) -> String {
+        let mut header = String::from("time,population,births,deaths");
+        for name in ["atp", "glucose", "size", "speed"] {
+            header += &format!(",{name}_mean,{name}_variance");
+        }
+        for id in columns {
+            header += &format!(",{}", id);
+        }
+        header
+    }
+
+    /// Writes the prevalence of each of `columns`, so rows always match the header even if
+    /// components were registered since.
+    fn csv_row(&self, columns: &[ComponentId]) -> String {
+        let mut row = format!(
+            "{},{},{},{}",
+            self.time, self.population, self.births, self.deaths
+        );
+        for summary in [self.atp, self.glucose, self.size, self.speed] {
+            row += &format!(",{},{}", summary.mean, summary.variance);
+        }
+        for id in columns {
+            row += &format!(",{}", self.components.get(id).copied().unwrap_or(0.));
+        }
+        row
+    }
+}
+
+/// Writes [StatsSample]s to `W` in one [StatsFormat].
+struct StatsWriter<W: Write> {
+    writer: W,
+    format: StatsFormat,
+    /// Component columns of the CSV header, fixed when the writer is created.
+    columns: Vec<ComponentId>,
+}
+
+impl<W: Write> StatsWriter<W> {
+    /// Writes the CSV header, with a column for each component in `first`.
+    fn new(mut writer: W, format: StatsFormat, first: &StatsSample) -> io::Result<Self> {
+        let mut columns = Vec::new();
+        if format == StatsFormat::Csv {
+            columns = first.components.keys().cloned().collect();
+            writeln!(writer, "{}", StatsSample::csv_header(&columns))?;
+        }
+
+        Ok(Self {
+            writer,
+            format,
+            columns,
+        })
+    }
+
+    fn write(&mut self, sample: &StatsSample) -> io::Result<()> {
+        match self.format {
+            StatsFormat::Csv => writeln!(self.writer, "{}", sample.csv_row(&self.columns))?,
+            StatsFormat::JsonLines => {
+                serde_json::to_writer(&mut self.writer, sample)?;
+                writeln!(self.writer)?;
+            }
+        }
+        // Flushed every sample so the file can be followed during a run.
+        self.writer.flush()
+    }
+}
+
+/// Writes a [StatsSample] every [StatsSettings::interval].
+#[derive(Resource, Default)]
+pub struct StatsRecorder {
+    writer: Option<StatsWriter<BufWriter<File>>>,
+    next_sample: f64,
+    births: u64,
+    deaths: u64,
+    /// Set once writing failed, so the error is only logged once.
+    failed: bool,
+}
+
+impl StatsRecorder {
+    /// Counts births and deaths from `lineage` and takes the next sample at `clock`. Called when
+    /// a snapshot replaces both, so samples neither underflow nor wait for the old clock.
+    pub fn resync(&mut self, lineage: &Lineage, clock: &SimClock) {
+        self.births = lineage.births();
+        self.deaths = lineage.deaths();
+        self.next_sample = clock.elapsed;
+    }
+
+    fn write(&mut self, path: &Path, sample: &StatsSample) -> io::Result<()> {
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => {
+                let file = BufWriter::new(File::create(path)?);
+                let writer = StatsWriter::new(file, StatsFormat::from_path(path), sample)?;
+                self.writer.insert(writer)
+            }
+        };
+
+        writer.write(sample)
+    }
+}
+
+pub fn record_stats(
+    settings: Res<StatsSettings>,
+    mut recorder: ResMut<StatsRecorder>,
+    cells: Query<&Cell>,
+    registry: Res<ComponentRegistry>,
+    lineage: Res<Lineage>,
+    clock: Res<SimClock>,
+) {
+    let Some(path) = &settings.path else {
+        return;
+    };
+    if recorder.failed || clock.elapsed < recorder.next_sample {
+        return;
+    }
+    recorder.next_sample = clock.elapsed + settings.interval;
+
+    let cells: Vec<&Cell> = cells.iter().collect();
+    let sample = StatsSample::new(
+        &cells,
+        &registry,
+        clock.elapsed,
+        lineage.births().saturating_sub(recorder.births),
+        lineage.deaths().saturating_sub(recorder.deaths),
+    );
+    recorder.births = lineage.births();
+    recorder.deaths = lineage.deaths();
+
+    if let Err(e) = recorder.write(path, &sample) {
+        log::error!("Could not record stats to {}: {}", path.display(), e);
+        recorder.failed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(time: f64, components: &[(&'static str, f32)]) -> StatsSample {
+        let summary = |mean| Summary {
+            mean,
+            variance: 0.5,
+        };
+        StatsSample {
+            time,
+            population: 4,
+            births: 2,
+            deaths: 1,
+            atp: summary(1.),
+            glucose: summary(2.),
+            size: summary(3.),
+            speed: summary(4.),
+            components: components
+                .iter()
+                .map(|(id, prevalence)| (ComponentId::new(id), *prevalence))
+                .collect(),
+        }
+    }
+
+    /// Two ticks, with a component registered between them.
+    fn written(format: StatsFormat) -> String {
+        let first = sample(0., &[("flagella", 0.25), ("burn_glucose", 1.)]);
+        let second = sample(
+            10.,
+            &[
+                ("flagella", 0.5),
+                ("burn_glucose", 0.75),
+                ("phagocytosis", 1.),
+            ],
+        );
+        let mut writer = StatsWriter::new(Vec::new(), format, &first).unwrap();
+        writer.write(&first).unwrap();
+        writer.write(&second).unwrap();
+        String::from_utf8(writer.writer).unwrap()
+    }
+
+    #[test]
+    fn csv_rows_match_the_header() {
+        assert_eq!(
+            written(StatsFormat::Csv),
+            "time,population,births,deaths,\
+             atp_mean,atp_variance,glucose_mean,glucose_variance,\
+             size_mean,size_variance,speed_mean,speed_variance,burn_glucose,flagella\n\
+             0,4,2,1,1,0.5,2,0.5,3,0.5,4,0.5,1,0.25\n\
+             10,4,2,1,1,0.5,2,0.5,3,0.5,4,0.5,0.75,0.5\n"
+        );
+    }
+
+    #[test]
+    fn json_lines_have_an_object_per_tick() {
+        let written = written(StatsFormat::JsonLines);
+        let lines: Vec<serde_json::Value> = written
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["time"], 0.);
+        assert_eq!(lines[1]["time"], 10.);
+        assert_eq!(lines[1]["population"], 4);
+        assert_eq!(lines[1]["atp"]["mean"], 1.);
+        assert_eq!(lines[1]["atp"]["variance"], 0.5);
+        // Unlike CSV columns, objects always list every component.
+        assert_eq!(lines[1]["components"]["phagocytosis"], 1.);
+        assert_eq!(lines[0]["components"].as_object().unwrap().len(), 2);
+    }
+}
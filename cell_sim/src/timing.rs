@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::plugin::{RapierConfiguration, TimestepMode};
+use serde::{Deserialize, Serialize};
+
+/// How simulated time relates to wall clock time. The simulation runs in [FixedUpdate], so a
+/// tick always simulates the same amount of time no matter how long frames take.
+#[derive(Resource, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct SimTiming {
+    /// Ticks per second of wall clock time.
+    pub tick_rate: f64,
+    /// Simulated time passed to the cells each tick.
+    pub sim_time_per_tick: f32,
+    /// Cell metabolism is integrated in steps of at most this much simulated time.
+    pub max_step: f32,
+    /// Most ticks run in a single frame to catch up after a slow frame. Anything beyond that is
+    /// dropped, so the simulation slows down instead of spiraling.
+    pub max_ticks_per_frame: u32,
+}
+
+impl Default for SimTiming {
+    /// One tick per frame at 60 FPS, simulating ten times faster than real time.
+    fn default() -> Self {
+        Self {
+            tick_rate: 60.,
+            sim_time_per_tick: 10. / 60.,
+            max_step: 0.01,
+            max_ticks_per_frame: 4,
+        }
+    }
+}
+
+impl SimTiming {
+    /// Number of metabolism steps per tick, and the simulated time of each step.
+    pub fn steps(&self) -> (u32, f32) {
+        let steps = (self.sim_time_per_tick / self.max_step).ceil().max(1.) as u32;
+        (steps, self.sim_time_per_tick / steps as f32)
+    }
+}
+
+/// Applies [SimTiming] to the fixed timestep and to Rapier. Rapier steps once per tick over the
+/// same simulated time as the cells, split into as many substeps as metabolism takes, so motion
+/// and metabolism stay in line at any sim speed.
+pub fn apply_sim_timing(
+    timing: Res<SimTiming>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    fixed_time.set_timestep_hz(timing.tick_rate);
+    virtual_time.set_max_delta(Duration::from_secs_f64(
+        timing.max_ticks_per_frame as f64 / timing.tick_rate,
+    ));
+    rapier_config.timestep_mode = TimestepMode::Fixed {
+        dt: timing.sim_time_per_tick,
+        substeps: timing.steps().0 as usize,
+    };
+}
+
+/// Simulated time since the run started. Survives snapshots, unlike [Time].
+#[derive(Resource, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct SimClock {
+    pub elapsed: f64,
+}
+
+pub fn advance_sim_clock(mut clock: ResMut<SimClock>, timing: Res<SimTiming>) {
+    clock.elapsed += timing.sim_time_per_tick as f64;
+}
@@ -0,0 +1,133 @@
+//! Splitting a cell's internals between mother and daughter, and taking pending daughters back,
+//! must conserve everything in it.
+
+use cell_sim::cell::*;
+
+fn internals() -> CellInternals {
+    CellInternals {
+        signal_proteins: (0..SIGNAL_PROTEINS)
+            .map(|i| SignalProtein::new(i as f32 * 0.5))
+            .collect(),
+        atp: 7.,
+        polysaccharides: vec![
+            Polysaccharide {
+                complexity: 10.,
+                amount: 3.,
+            },
+            Polysaccharide {
+                complexity: 20.,
+                amount: 1.5,
+            },
+        ],
+        glucose: 2.,
+        proteins: 0.3,
+        nucleotides: 0.7,
+        amino_acids: 1.1,
+    }
+}
+
+/// Every amount in the internals, in a fixed order.
+fn amounts(internals: &CellInternals) -> Vec<f32> {
+    let mut amounts = vec![
+        internals.atp,
+        internals.glucose,
+        internals.proteins,
+        internals.nucleotides,
+        internals.amino_acids,
+    ];
+    amounts.extend(internals.polysaccharides.iter().map(|p| p.amount));
+    amounts.extend(internals.signal_proteins.iter().map(|s| s.amount()));
+    amounts
+}
+
+fn assert_conserved(original: &CellInternals, mother: &CellInternals, daughter: &CellInternals) {
+    assert_eq!(mother.polysaccharides.len(), original.polysaccharides.len());
+    assert_eq!(
+        daughter.polysaccharides.len(),
+        original.polysaccharides.len()
+    );
+    assert_eq!(
+        daughter.signal_proteins.len(),
+        original.signal_proteins.len()
+    );
+    for ((original, mother), daughter) in original
+        .polysaccharides
+        .iter()
+        .zip(&mother.polysaccharides)
+        .zip(&daughter.polysaccharides)
+    {
+        assert_eq!(mother.complexity, original.complexity);
+        assert_eq!(daughter.complexity, original.complexity);
+    }
+
+    for ((original, mother), daughter) in amounts(original)
+        .into_iter()
+        .zip(amounts(mother))
+        .zip(amounts(daughter))
+    {
+        assert!(
+            (mother + daughter - original).abs() <= 1e-5,
+            "{} + {} != {}",
+            mother,
+            daughter,
+            original
+        );
+    }
+}
+
+#[test]
+fn split_off_conserves_every_field() {
+    for ratio in [0., 0.25, 0.5, 0.9, 1.] {
+        let original = internals();
+        let mut mother = original.clone();
+        let daughter = mother.split_off(ratio);
+        assert_conserved(&original, &mother, &daughter);
+        assert!((daughter.atp - original.atp * ratio).abs() <= 1e-5);
+    }
+}
+
+#[test]
+fn split_off_clamps_ratio() {
+    let original = internals();
+
+    let mut mother = original.clone();
+    let daughter = mother.split_off(1.5);
+    assert_conserved(&original, &mother, &daughter);
+    assert!(amounts(&mother).iter().all(|amount| *amount == 0.));
+
+    let mut mother = original.clone();
+    let daughter = mother.split_off(-0.5);
+    assert_conserved(&original, &mother, &daughter);
+    assert!(amounts(&daughter).iter().all(|amount| *amount == 0.));
+}
+
+#[test]
+fn pending_offspring_are_reabsorbed() {
+    let original = internals();
+    let mut cell = Cell::new(
+        RNA::new(),
+        Lineage::default().birth(None, None, 0.),
+        SimRng::new(0).cell_rng(),
+    );
+    cell.data.base = original.clone();
+    let daughter = cell.data.base.split_off(0.5);
+    cell.data.offspring.push(Offspring {
+        rna: RNA::new(),
+        internals: daughter,
+    });
+
+    cell.data.reabsorb_offspring();
+    assert!(cell.data.offspring.is_empty());
+    assert_eq!(
+        cell.data.base.polysaccharides.len(),
+        original.polysaccharides.len()
+    );
+    for (amount, original) in amounts(&cell.data.base).into_iter().zip(amounts(&original)) {
+        assert!(
+            (amount - original).abs() <= 1e-5,
+            "{} != {}",
+            amount,
+            original
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Updates many cells in parallel and checks they end up exactly where updating them one by one
+//! gets them. Every cell owns its state and random stream, so the order cells are updated in must
+//! not matter.
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+use cell_sim::cell::*;
+use cell_sim::scene::WorldBounds;
+use cell_sim::timing::{SimClock, SimTiming};
+use cell_sim::CellSimSettings;
+
+const CELLS: usize = 200;
+const TICKS: usize = 20;
+
+fn world(seed: u64) -> World {
+    let mut world = World::new();
+    world.insert_resource(CellSimSettings {
+        spawn_count: CELLS,
+        ..Default::default()
+    });
+    world.insert_resource(WorldBounds::default());
+    world.insert_resource(SeedGenomes::default());
+    world.insert_resource(SimRng::new(seed));
+    world.insert_resource(SimTiming::default());
+    world.insert_resource(SimClock::default());
+    world.init_resource::<ComponentRegistry>();
+    world.init_resource::<Lineage>();
+    world.run_system_once(spawn_cells);
+    world
+}
+
+/// Gives every cell the same glucose to take each tick, so uptake is exercised too.
+fn feed(world: &mut World) {
+    for mut cell in world.query::<&mut Cell>().iter_mut(world) {
+        cell.data.surroundings.glucose = 1.;
+    }
+}
+
+/// Everything a cell's components write to, bit for bit.
+fn state(world: &mut World) -> Vec<(CellId, Vec<u32>, usize, usize)> {
+    let mut state: Vec<_> = world
+        .query::<&Cell>()
+        .iter(world)
+        .map(|cell| {
+            let data = &cell.data;
+            let mut values = vec![
+                data.base.atp,
+                data.base.glucose,
+                data.base.proteins,
+                data.base.amino_acids,
+                data.base.nucleotides,
+                data.velocity.x,
+                data.velocity.y,
+                data.angular_velocity,
+                data.size,
+                data.engulf,
+                data.surroundings.glucose_taken,
+            ];
+            values.extend(data.base.polysaccharides.iter().map(|p| p.amount));
+            values.extend(data.base.signal_proteins.iter().map(|s| s.amount()));
+            for component in cell
+                .internal_components
+                .iter()
+                .chain(&cell.membrane_components)
+            {
+                values.extend(&component.state);
+            }
+            (
+                data.lineage.id,
+                values.into_iter().map(f32::to_bits).collect(),
+                data.offspring.len(),
+                cell.internal_components.len() + cell.membrane_components.len(),
+            )
+        })
+        .collect();
+    state.sort_by_key(|(id, ..)| *id);
+    state
+}
+
+#[test]
+fn parallel_update_matches_sequential_update() {
+    ComputeTaskPool::get_or_init(TaskPool::default);
+
+    let mut parallel = world(42);
+    let mut schedule = Schedule::default();
+    schedule.add_systems(metabolize_cells);
+    for _ in 0..TICKS {
+        feed(&mut parallel);
+        schedule.run(&mut parallel);
+    }
+
+    let mut sequential = world(42);
+    let (steps, dt) = sequential.resource::<SimTiming>().steps();
+    for _ in 0..TICKS {
+        feed(&mut sequential);
+        for mut cell in sequential.query::<&mut Cell>().iter_mut(&mut sequential) {
+            cell.metabolize(steps, dt);
+        }
+    }
+
+    let parallel = state(&mut parallel);
+    assert_eq!(parallel.len(), CELLS);
+    assert_eq!(parallel, state(&mut sequential));
+}
@@ -0,0 +1,164 @@
+//! Runs the core reactions on a bare [CellData] and checks they only run as far as their inputs
+//! last and that what they make scales with the component's efficiency.
+
+use std::sync::Arc;
+
+use cell_sim::cell::*;
+
+/// Long enough for every reaction to use up its inputs in one step.
+const DT: f32 = 100.;
+
+fn component(id: &'static str, proteins: f32) -> CellComponent {
+    let info = core_components()
+        .into_iter()
+        .find(|info| info.id == ComponentId::new(id))
+        .unwrap();
+    // A single weight at full strength, sensitive to a signal that does not exist.
+    let weightlist = WeightList::new(vec![Weight {
+        index: 0.,
+        range: 1.,
+        base: 100.,
+        sensitivity: Sensitivity {
+            index: usize::MAX,
+            weight: 0.,
+        },
+    }]);
+    let spec = ComponentSpec {
+        id: info.id.clone(),
+        props: ComponentBuilderProps {
+            size: 1.,
+            proteins,
+            weightlist,
+        },
+    };
+
+    CellComponent::new(Arc::new(info), spec)
+}
+
+fn cell_data(internals: CellInternals) -> CellData {
+    let mut cell = Cell::new(
+        RNA::new(),
+        Lineage::default().birth(None, None, 0.),
+        SimRng::new(0).cell_rng(),
+    );
+    cell.data.base = internals;
+    cell.data
+}
+
+fn empty() -> CellInternals {
+    CellInternals {
+        signal_proteins: Vec::new(),
+        atp: 0.,
+        polysaccharides: Vec::new(),
+        glucose: 0.,
+        proteins: 0.,
+        nucleotides: 0.,
+        amino_acids: 0.,
+    }
+}
+
+fn polysaccharides(data: &CellData) -> f32 {
+    data.base.polysaccharides.iter().map(|p| p.amount).sum()
+}
+
+fn assert_close(a: f32, b: f32) {
+    assert!((a - b).abs() <= 1e-5, "{} != {}", a, b);
+}
+
+#[test]
+fn glycolysis_uses_up_glucose_and_scales_atp_with_efficiency() {
+    for proteins in [0.1, 0.5, 2.] {
+        let component = component("burn_glucose", proteins);
+        let mut data = cell_data(CellInternals {
+            glucose: 1.5,
+            ..empty()
+        });
+        component.run(&mut data, DT, 0);
+
+        assert_close(data.base.glucose, 0.);
+        assert_close(data.base.atp, 1.5 * component.efficiency);
+        // Byproducts are not scaled by efficiency.
+        assert_close(data.base.amino_acids, 0.15);
+    }
+}
+
+#[test]
+fn polysaccharide_synthesis_stops_when_atp_runs_out() {
+    let component = component("create_polysaccharides", 1.);
+    let mut data = cell_data(CellInternals {
+        glucose: 2.,
+        atp: 0.005,
+        ..empty()
+    });
+    component.run(&mut data, DT, 0);
+
+    // 0.005 ATP lasts for 0.5 units of glucose.
+    assert!(data.base.atp >= 0.);
+    assert_close(data.base.atp, 0.);
+    assert_close(data.base.glucose, 1.5);
+    assert_close(polysaccharides(&data), 0.5 * component.efficiency);
+    assert!(data
+        .base
+        .polysaccharides
+        .iter()
+        .all(|p| p.complexity == POLYSACCHARIDE_COMPLEXITY));
+}
+
+#[test]
+fn polysaccharide_digestion_scales_glucose_with_efficiency() {
+    for proteins in [0.1, 2.] {
+        let component = component("reduce_polysaccharides", proteins);
+        let mut data = cell_data(CellInternals {
+            polysaccharides: vec![
+                Polysaccharide {
+                    complexity: POLYSACCHARIDE_COMPLEXITY,
+                    amount: 0.5,
+                },
+                Polysaccharide {
+                    complexity: POLYSACCHARIDE_COMPLEXITY,
+                    amount: 0.25,
+                },
+            ],
+            ..empty()
+        });
+        component.run(&mut data, DT, 0);
+
+        assert!(data.base.polysaccharides.is_empty());
+        assert_close(
+            data.base.glucose,
+            0.75 * POLYSACCHARIDE_COMPLEXITY * component.efficiency,
+        );
+    }
+}
+
+#[test]
+fn glucose_uptake_takes_no_more_than_the_surroundings_offer() {
+    let component = component("glucose_uptake", 1.);
+    let mut data = cell_data(empty());
+    data.surroundings.glucose = 0.3;
+    component.run(&mut data, DT, 0);
+
+    assert_close(data.surroundings.glucose, 0.);
+    assert_close(data.surroundings.glucose_taken, 0.3);
+    assert_close(data.base.glucose, 0.3 * component.efficiency);
+}
+
+#[test]
+fn reactions_without_inputs_do_nothing() {
+    for id in [
+        "burn_glucose",
+        "create_polysaccharides",
+        "reduce_polysaccharides",
+        "glucose_uptake",
+    ] {
+        let component = component(id, 1.);
+        let mut data = cell_data(empty());
+        component.run(&mut data, DT, 0);
+
+        assert_eq!(data.base.atp, 0.);
+        assert_eq!(data.base.glucose, 0.);
+        assert_eq!(data.base.amino_acids, 0.);
+        assert_eq!(polysaccharides(&data), 0.);
+        assert_eq!(data.surroundings.glucose_taken, 0.);
+    }
+}
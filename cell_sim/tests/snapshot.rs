@@ -0,0 +1,103 @@
+//! Saves a world, restores it into a fresh one and checks nothing was lost: the same cells, random
+//! streams and fields, and the same run from there on.
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+use cell_sim::cell::*;
+use cell_sim::nutrients::{NutrientField, NutrientSettings};
+use cell_sim::scene::WorldBounds;
+use cell_sim::signals::{SignalField, SignalSettings};
+use cell_sim::timing::{SimClock, SimTiming};
+use cell_sim::CellSimSettings;
+
+fn world() -> World {
+    let mut world = World::new();
+    world.insert_resource(CellSimSettings {
+        spawn_count: 50,
+        ..Default::default()
+    });
+    let bounds = WorldBounds::default();
+    let nutrient_settings = NutrientSettings::default();
+    let nutrients = NutrientField::new(&bounds, &nutrient_settings);
+    world.insert_resource(SignalField::new(&nutrients));
+    world.insert_resource(nutrients);
+    world.insert_resource(nutrient_settings);
+    world.insert_resource(bounds);
+    world.insert_resource(SignalSettings::default());
+    world.insert_resource(SeedGenomes::default());
+    world.insert_resource(SimRng::new(7));
+    world.insert_resource(SimTiming::default());
+    world.insert_resource(SimClock::default());
+    world.insert_resource(MutationRates::default());
+    world.insert_resource(ReproductionMode::Sexual);
+    world.init_resource::<ComponentRegistry>();
+    world.init_resource::<Lineage>();
+    world
+}
+
+fn run(world: &mut World, ticks: usize) {
+    let mut schedule = Schedule::default();
+    schedule.add_systems(metabolize_cells);
+    for _ in 0..ticks {
+        schedule.run(world);
+    }
+}
+
+/// The snapshot of `world` as RON, with cells in a fixed order.
+fn saved(world: &mut World) -> String {
+    let mut snapshot = take_snapshot(world);
+    snapshot.cells.sort_by_key(|cell| cell.data.lineage.id);
+    ron::to_string(&snapshot).unwrap()
+}
+
+/// Goes through a file named after `test`, so tests running at the same time do not share one.
+fn save_and_load(world: &mut World, test: &str, extension: &str) -> WorldSnapshot {
+    let path = std::env::temp_dir().join(format!(
+        "cell_sim_{}_{}.{}",
+        test,
+        std::process::id(),
+        extension
+    ));
+    save_snapshot(&path, &take_snapshot(world)).unwrap();
+    let snapshot = load_snapshot(&path);
+    std::fs::remove_file(&path).unwrap();
+    snapshot.unwrap()
+}
+
+#[test]
+fn restored_world_matches_saved_world() {
+    ComputeTaskPool::get_or_init(TaskPool::default);
+
+    let mut original = world();
+    original.run_system_once(spawn_cells);
+    run(&mut original, 5);
+
+    for extension in ["ron", "bin"] {
+        let mut restored = world();
+        restore_snapshot(
+            &mut restored,
+            save_and_load(&mut original, "matches", extension),
+        );
+        assert_eq!(saved(&mut restored), saved(&mut original));
+    }
+}
+
+#[test]
+fn restored_world_keeps_running_the_same() {
+    ComputeTaskPool::get_or_init(TaskPool::default);
+
+    let mut original = world();
+    original.run_system_once(spawn_cells);
+    run(&mut original, 5);
+    let mut restored = world();
+    restore_snapshot(
+        &mut restored,
+        save_and_load(&mut original, "keeps_running", "bin"),
+    );
+
+    // Random streams pick up where they left off.
+    run(&mut original, 5);
+    run(&mut restored, 5);
+    assert_eq!(saved(&mut restored), saved(&mut original));
+}